@@ -0,0 +1,521 @@
+/*
+What mobs get up to. Each mob's behaviour is a small state machine moved on by what it can sense,
+which then gives it somewhere to head for. Paths there come from the pathfinder, and the mob is
+steered along them a block at a time.
+Random choices come from the mob's own rng, so a seed always plays out the same way.
+*/
+
+use std::time::Duration;
+
+use cgmath::{InnerSpace, MetricSpace, Point3, Quaternion, Rad, Rotation3, Vector3};
+use hecs::Entity;
+use rand::{Rng, rngs::SmallRng};
+
+use crate::{
+    entity::components::{Behaviour, Brain, Health, Movement, Orientation, Position, Vision},
+    math::bbox::AABB,
+    pathfinding::{Mobility, PathStatus, Pathfinder, can_stand, is_solid},
+    state::world::{BlockPos, World, WorldPos},
+};
+
+/// Seconds spent standing around between wanders
+const IDLE_TIME: std::ops::Range<f32> = 2.0..6.0;
+/// Furthest a wander goes, in blocks along each axis
+const WANDER_RANGE: i32 = 8;
+/// Fraction of health left at which mobs run rather than chase
+const FLEE_HEALTH: f32 = 0.25;
+/// How close to a wander target counts as getting there
+const ARRIVE_DISTANCE: f32 = 0.1;
+/// Seconds to wait before looking for a way again after finding there isn't one
+const RETRY_TIME: f32 = 2.;
+/// Blocks a goal can move, eg. a chased player walking, before the path there is found again
+const REPATH_DISTANCE: i32 = 2;
+/// Blocks per second squared, the same as the player falls at
+const GRAVITY: f32 = 10.;
+
+/// What a mob knows about its surroundings this tick
+#[derive(Debug, Clone)]
+pub struct Senses {
+    pub pos: WorldPos,
+    /// Fraction of health left
+    pub health: f32,
+    /// The player & where they are, if they're within sight
+    pub player: Option<(Entity, WorldPos)>,
+    /// No way to the last goal could be found
+    pub stuck: bool,
+}
+
+/// Work out what a mob does next, `seconds` after it last did
+pub fn next_behaviour(
+    current: &Behaviour,
+    senses: &Senses,
+    rng: &mut SmallRng,
+    seconds: f32,
+) -> Behaviour {
+    if let Some((player, _)) = senses.player {
+        return match senses.health <= FLEE_HEALTH {
+            true => Behaviour::Fleeing(player),
+            false => Behaviour::Persuing(player),
+        };
+    }
+
+    match current {
+        Behaviour::Idle(left) if *left > seconds => Behaviour::Idle(left - seconds),
+        Behaviour::Idle(_) => Behaviour::Wandering(wander_target(&senses.pos, rng)),
+        Behaviour::Wandering(target)
+            if !senses.stuck && horizontal(&senses.pos, target).magnitude() > ARRIVE_DISTANCE =>
+        {
+            Behaviour::Wandering(*target)
+        }
+        // Got there, can't get there or lost sight of the player
+        _ => Behaviour::Idle(rng.random_range(IDLE_TIME)),
+    }
+}
+
+/// Middle of a random block column near a position
+fn wander_target(pos: &WorldPos, rng: &mut SmallRng) -> WorldPos {
+    let block = pos.to_block_pos();
+    let mut offset = || rng.random_range(-WANDER_RANGE..=WANDER_RANGE) as f32 + 0.5;
+    let (x, z) = (offset(), offset());
+    WorldPos(Point3::new(
+        block.0.x as f32 + x,
+        pos.0.y,
+        block.0.z as f32 + z,
+    ))
+}
+
+/// Where a behaviour wants the mob to be, if anywhere
+pub fn goal_of(behaviour: &Behaviour, senses: &Senses) -> Option<WorldPos> {
+    match (behaviour, senses.player) {
+        (Behaviour::Wandering(target), _) => Some(*target),
+        (Behaviour::Persuing(_), Some((_, player))) => Some(player),
+        (Behaviour::Fleeing(_), Some((_, player))) => {
+            let away = horizontal(&player, &senses.pos);
+            let away = match away.magnitude2() > 0. {
+                true => away.normalize(),
+                false => Vector3::unit_x(),
+            };
+            Some(senses.pos + away * WANDER_RANGE as f32)
+        }
+        _ => None,
+    }
+}
+
+/// Sideways distance from one position to another
+fn horizontal(from: &WorldPos, to: &WorldPos) -> Vector3<f32> {
+    let diff = to.0 - from.0;
+    Vector3::new(diff.x, 0., diff.z)
+}
+
+/// The block in a column somewhere around a position which a mob could stand in
+fn ground_near(
+    pos: &WorldPos,
+    mobility: &Mobility,
+    is_solid: impl Fn(&BlockPos) -> bool,
+) -> Option<BlockPos> {
+    let block = pos.to_block_pos();
+    let below = (mobility.max_fall + mobility.height) as i32;
+    (-below..=mobility.step_up as i32)
+        .rev()
+        .map(|dy| &block + Vector3::new(0, dy, 0))
+        .find(|pos| can_stand(pos, mobility, &is_solid))
+}
+
+/// Keep the brain's path heading for the goal, finding a new one if the goal's moved much.
+/// After finding there's no way there, it waits a while before searching again.
+fn plan(
+    brain: &mut Brain,
+    feet: &BlockPos,
+    goal: Option<BlockPos>,
+    mobility: &Mobility,
+    paths: &mut Pathfinder,
+    is_solid: impl Fn(&BlockPos) -> bool,
+    seconds: f32,
+) {
+    brain.retry_in = (brain.retry_in - seconds).max(0.);
+    let Some(goal) = goal else {
+        brain.goal = None;
+        brain.path.clear();
+        brain.stuck = false;
+        return;
+    };
+    if brain.stuck && brain.retry_in > 0. {
+        return;
+    }
+
+    // Following a path to close enough, or already there
+    let nearby = brain.goal.as_ref().is_some_and(|old| {
+        let diff = goal.0 - old.0;
+        diff.x.abs() + diff.y.abs() + diff.z.abs() <= REPATH_DISTANCE
+    });
+    if !brain.stuck && nearby && (!brain.path.is_empty() || *feet == goal) {
+        return;
+    }
+
+    match paths.find_path(feet, &goal, mobility, is_solid) {
+        PathStatus::Found(path) => {
+            brain.goal = Some(goal);
+            brain.path = path;
+            brain.stuck = false;
+        }
+        // Carry on along the old path while waiting
+        PathStatus::Pending => {}
+        PathStatus::NoPath => {
+            brain.goal = Some(goal);
+            brain.path.clear();
+            brain.stuck = true;
+            brain.retry_in = RETRY_TIME;
+        }
+    }
+}
+
+/// Walk up to `distance` along the brain's path, turning to face the way it's going.
+/// If a block's been put in the way since the path was found, it stops & drops the path.
+fn steer(
+    position: &mut Position,
+    orientation: &mut Orientation,
+    brain: &mut Brain,
+    mut distance: f32,
+    mobility: &Mobility,
+    is_solid: impl Fn(&BlockPos) -> bool,
+) {
+    while distance > 0.
+        && let Some(next) = brain.path.first()
+    {
+        if !can_stand(next, mobility, &is_solid) {
+            brain.path.clear();
+            brain.goal = None;
+            return;
+        }
+
+        let target = Point3::new(
+            next.0.x as f32 + 0.5,
+            next.0.y as f32,
+            next.0.z as f32 + 0.5,
+        );
+        let across = horizontal(&position.0, &WorldPos(target));
+        if across.magnitude2() > 0. {
+            orientation.0 = Quaternion::from_angle_y(Rad(across.x.atan2(across.z)));
+        }
+
+        let remaining = across.magnitude();
+        if remaining <= distance {
+            position.0.0 = target;
+            distance -= remaining;
+            brain.path.remove(0);
+        } else {
+            position.0.0 += across.normalize() * distance;
+            // Step up straight away, but don't drop until over the lower block
+            position.0.0.y = position.0.0.y.max(target.y);
+            distance = 0.;
+        }
+    }
+}
+
+/// Pull a mob down, landing it on top of the first solid block under its feet.
+/// Returns whether it was already stood on the ground, rather than in the air or only just landing.
+/// Flyers never fall.
+fn fall(
+    position: &mut Position,
+    movement: &mut Movement,
+    mobility: &Mobility,
+    is_solid: impl Fn(&BlockPos) -> bool,
+    seconds: f32,
+) -> bool {
+    if mobility.flying {
+        movement.vertical_velocity = 0.;
+        return true;
+    }
+
+    movement.vertical_velocity -= GRAVITY * seconds;
+    let y = position.0.0.y;
+    let new_y = y + movement.vertical_velocity * seconds;
+
+    // Highest block it would fall through the top of
+    let feet = position.0.to_block_pos();
+    let ground = (new_y.floor() as i32 - 1..feet.0.y)
+        .rev()
+        .map(|block_y| BlockPos::new(feet.0.x, block_y, feet.0.z))
+        .find(|pos| is_solid(pos) && (pos.0.y + 1) as f32 >= new_y);
+
+    match ground {
+        Some(ground) => {
+            position.0.0.y = (ground.0.y + 1) as f32;
+            movement.vertical_velocity = 0.;
+            (ground.0.y + 1) as f32 >= y
+        }
+        None => {
+            position.0.0.y = new_y;
+            false
+        }
+    }
+}
+
+/// Move every mob's behaviour on, then walk them towards wherever it wants them to be
+pub fn behaviour_tick(
+    ecs: &mut hecs::World,
+    world: &World,
+    paths: &mut Pathfinder,
+    player: Entity,
+    duration: &Duration,
+) {
+    let seconds = duration.as_secs_f32();
+    let player_pos = ecs.get::<&WorldPos>(player).ok().map(|pos| *pos);
+    let is_solid = |pos: &BlockPos| is_solid(world, pos);
+
+    for (_, mob) in ecs.query_mut::<(
+        &mut Position,
+        &mut Orientation,
+        &AABB<f32>,
+        &Health,
+        &mut Movement,
+        &Vision,
+        &mut Behaviour,
+        &mut Brain,
+    )>() {
+        let (position, orientation, hitbox, health, movement, vision, behaviour, brain) = mob;
+        let senses = Senses {
+            pos: position.0,
+            health: health.current / health.max,
+            player: player_pos
+                .filter(|pos| pos.0.distance(position.0.0) <= vision.0)
+                .map(|pos| (player, pos)),
+            stuck: brain.stuck,
+        };
+        *behaviour = next_behaviour(behaviour, &senses, &mut brain.rng, seconds);
+
+        let mobility = Mobility {
+            height: (hitbox.end.y - hitbox.start.y).ceil().max(1.) as u32,
+            ..Default::default()
+        };
+
+        // Paths start from the ground, so a new one's needed once it's landed
+        if !fall(position, movement, &mobility, is_solid, seconds) {
+            brain.path.clear();
+            continue;
+        }
+
+        let goal =
+            goal_of(behaviour, &senses).and_then(|goal| ground_near(&goal, &mobility, is_solid));
+        let feet = position.0.to_block_pos();
+        plan(brain, &feet, goal, &mobility, paths, is_solid, seconds);
+        steer(
+            position,
+            orientation,
+            brain,
+            movement.speed * seconds,
+            &mobility,
+            is_solid,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Point3;
+    use hecs::World;
+    use rand::{SeedableRng, rngs::SmallRng};
+    use rustc_hash::FxHashSet;
+
+    use crate::{
+        behaviour::{FLEE_HEALTH, RETRY_TIME, Senses, fall, next_behaviour, plan, steer},
+        entity::components::{Behaviour, Brain, Movement, Orientation, Position},
+        pathfinding::{Mobility, Pathfinder},
+        state::world::{BlockPos, WorldPos},
+    };
+
+    #[test]
+    fn test_transitions() {
+        let player = World::new().spawn(());
+        let senses = Senses {
+            pos: WorldPos(Point3::new(0.5, 0., 0.5)),
+            health: 1.,
+            player: None,
+            stuck: false,
+        };
+
+        // Idles until the timer runs out, then picks the same place to wander to for the same seed
+        let wander = |seed| {
+            let mut rng = SmallRng::seed_from_u64(seed);
+            let idle = next_behaviour(&Behaviour::Idle(1.), &senses, &mut rng, 0.5);
+            assert!(matches!(idle, Behaviour::Idle(left) if left == 0.5));
+            match next_behaviour(&idle, &senses, &mut rng, 0.5) {
+                Behaviour::Wandering(target) => target.0,
+                other => panic!("Didn't start wandering: {other:?}"),
+            }
+        };
+        assert_eq!(wander(7), wander(7));
+
+        // Keeps going until it gets there or gets stuck
+        let target = WorldPos(Point3::new(4.5, 0., 0.5));
+        let mut rng = SmallRng::seed_from_u64(7);
+        let wandering = Behaviour::Wandering(target);
+        assert!(matches!(
+            next_behaviour(&wandering, &senses, &mut rng, 0.1),
+            Behaviour::Wandering(_)
+        ));
+        let stuck = Senses {
+            stuck: true,
+            ..senses.clone()
+        };
+        assert!(matches!(
+            next_behaviour(&wandering, &stuck, &mut rng, 0.1),
+            Behaviour::Idle(_)
+        ));
+
+        // Chases the player when it sees them, unless it's hurt, & gives up when they're gone
+        let seen = Senses {
+            player: Some((player, target)),
+            ..senses.clone()
+        };
+        assert!(matches!(
+            next_behaviour(&Behaviour::Idle(3.), &seen, &mut rng, 0.1),
+            Behaviour::Persuing(e) if e == player
+        ));
+        let hurt = Senses {
+            health: FLEE_HEALTH,
+            ..seen
+        };
+        let fleeing = next_behaviour(&Behaviour::Persuing(player), &hurt, &mut rng, 0.1);
+        assert!(matches!(fleeing, Behaviour::Fleeing(e) if e == player));
+        assert!(matches!(
+            next_behaviour(&fleeing, &senses, &mut rng, 0.1),
+            Behaviour::Idle(_)
+        ));
+    }
+
+    #[test]
+    fn test_steering() {
+        // Floor along y = -1 with a step up at x = 3
+        let mut solid = (0..6)
+            .map(|x| BlockPos::new(x, -1, 0))
+            .collect::<FxHashSet<_>>();
+        solid.insert(BlockPos::new(3, 0, 0));
+        let is_solid = |pos: &BlockPos| solid.contains(pos);
+
+        let mobility = Mobility::default();
+        let mut paths = Pathfinder::default();
+        let mut brain = Brain::seeded(0);
+        let mut position = Position(WorldPos(Point3::new(0.5, 0., 0.5)));
+        let mut orientation = Orientation::default();
+        let goal = BlockPos::new(5, 0, 0);
+
+        plan(
+            &mut brain,
+            &BlockPos::new(0, 0, 0),
+            Some(goal.clone()),
+            &mobility,
+            &mut paths,
+            is_solid,
+            0.,
+        );
+        assert_eq!(brain.path.last(), Some(&goal));
+
+        // Climbs the step on the way
+        steer(
+            &mut position,
+            &mut orientation,
+            &mut brain,
+            3.,
+            &mobility,
+            is_solid,
+        );
+        assert_eq!(position.0.0, Point3::new(3.5, 1., 0.5));
+        steer(
+            &mut position,
+            &mut orientation,
+            &mut brain,
+            10.,
+            &mobility,
+            is_solid,
+        );
+        assert_eq!(position.0.to_block_pos(), goal);
+        assert!(brain.path.is_empty());
+
+        // Walking back, a block put in the way stops it rather than walking into it
+        plan(
+            &mut brain,
+            &goal,
+            Some(BlockPos::new(0, 0, 0)),
+            &mobility,
+            &mut paths,
+            is_solid,
+            0.,
+        );
+        solid.insert(BlockPos::new(4, 1, 0));
+        let is_solid = |pos: &BlockPos| solid.contains(pos);
+        steer(
+            &mut position,
+            &mut orientation,
+            &mut brain,
+            10.,
+            &mobility,
+            is_solid,
+        );
+        assert_eq!(position.0.to_block_pos(), goal);
+        assert!(brain.path.is_empty());
+    }
+
+    #[test]
+    fn test_falling() {
+        // Ledge at y = 2 above the floor at y = -1
+        let mut solid = FxHashSet::from_iter([BlockPos::new(0, -1, 0), BlockPos::new(1, 1, 0)]);
+        let is_solid = |pos: &BlockPos| solid.contains(pos);
+
+        let mobility = Mobility::default();
+        let mut movement = Movement {
+            speed: 1.,
+            vertical_velocity: 0.,
+        };
+        let mut position = Position(WorldPos(Point3::new(1.5, 2., 0.5)));
+
+        // Stays put on the ledge
+        assert!(fall(&mut position, &mut movement, &mobility, is_solid, 0.1));
+        assert_eq!(position.0.0.y, 2.);
+        assert_eq!(movement.vertical_velocity, 0.);
+
+        // Walked off the edge, it drops & lands on the floor
+        position.0.0.x = 0.5;
+        let stood = fall(&mut position, &mut movement, &mobility, is_solid, 0.1);
+        assert!(!stood);
+        assert!(position.0.0.y < 2.);
+        let landed = (0..100).any(|_| fall(&mut position, &mut movement, &mobility, is_solid, 0.1));
+        assert!(landed);
+        assert_eq!(position.0.0.y, 0.);
+
+        // Same again when the block it's stood on is broken
+        solid.remove(&BlockPos::new(0, -1, 0));
+        solid.insert(BlockPos::new(0, -3, 0));
+        let is_solid = |pos: &BlockPos| solid.contains(pos);
+        while !fall(&mut position, &mut movement, &mobility, is_solid, 0.1) {}
+        assert_eq!(position.0.0.y, -2.);
+    }
+
+    #[test]
+    fn test_stuck_backoff() {
+        // Floor with a wall too high to climb in the way
+        let mut solid = (0..10)
+            .map(|x| BlockPos::new(x, -1, 0))
+            .collect::<FxHashSet<_>>();
+        solid.extend((0..3).map(|y| BlockPos::new(5, y, 0)));
+        let is_solid = |pos: &BlockPos| solid.contains(pos);
+
+        let mobility = Mobility::default();
+        let mut paths = Pathfinder::default();
+        let mut brain = Brain::seeded(0);
+        let chase = |brain: &mut Brain, paths: &mut Pathfinder, x, seconds| {
+            paths.new_frame();
+            let goal = Some(BlockPos::new(x, 0, 0));
+            let start = BlockPos::new(0, 0, 0);
+            plan(brain, &start, goal, &mobility, paths, is_solid, seconds);
+            paths.spent()
+        };
+        assert!(chase(&mut brain, &mut paths, 9, 0.1) > 0);
+        assert!(brain.stuck);
+
+        // The player moving doesn't start a new search until it's waited a while
+        assert_eq!(chase(&mut brain, &mut paths, 8, 0.1), 0);
+        assert!(brain.stuck);
+        assert!(chase(&mut brain, &mut paths, 7, RETRY_TIME) > 0);
+    }
+}
@@ -4,16 +4,23 @@ use std::{sync::Arc, time::Instant};
 use game_engine::{
     InteractionMode,
     camera::{Controller, traits::PlayerController},
-    entity::{components::Hotbar, systems::spawn_player},
+    entity::{
+        components::{Hotbar, ItemViews},
+        systems::spawn_player,
+    },
     event::{
         MESSAGE_QUEUE, Message, Subscriber,
         messages::{
-            ItemFavouritedMessage, TransferItemMessage, TransferItemRequestMessage,
-            TransferItemSource,
+            ItemFavouritedMessage, SetItemViewMessage, TransferItemMessage,
+            TransferItemRequestMessage, TransferItemSource,
         },
     },
+    pathfinding::Pathfinder,
+    power::PowerGrid,
     render::state::RenderState,
     state::{game::GameState, world::World},
+    storage::{StorageGrid, route_transfer},
+    tick::TickScheduler,
     ui::debug::DEBUG_WINDOW,
     util::stopwatch::StopWatch,
 };
@@ -45,6 +52,11 @@ impl App {
             player: player_entity,
             entities: vec![],
             ecs,
+            power: PowerGrid::default(),
+            ticks: TickScheduler::default(),
+            storage: StorageGrid::default(),
+            paths: Pathfinder::default(),
+            active_chunks: Default::default(),
         };
         game_state.init();
 
@@ -91,6 +103,7 @@ impl App {
                     item,
                     count,
                     source,
+                    source_side,
                 }) => {
                     let interaction_mode = &*self
                         .game_state
@@ -98,44 +111,43 @@ impl App {
                         .get::<&InteractionMode>(self.game_state.player)
                         .unwrap();
 
-                    if let Some((source, dest)) = match (source, interaction_mode) {
+                    if let Some((source, dest, source_side)) = match (source, interaction_mode) {
                         // Player -> Block
                         (TransferItemSource::Inventory, InteractionMode::Block(target_block)) => {
-                            let dest = *self
-                                .game_state
+                            self.game_state
                                 .world
                                 .block_states
                                 .get(target_block)
-                                .expect("Tried to transfer to non-stateful block!");
-
-                            Some((self.game_state.player, dest))
+                                .map(|dest| (self.game_state.player, *dest, None))
                         }
                         // Block -> Player
                         (
                             TransferItemSource::Block(source_block),
                             InteractionMode::Block(target_block),
-                        ) if source_block == target_block => {
-                            let source = *self
-                                .game_state
-                                .world
-                                .block_states
-                                .get(source_block)
-                                .expect("Tried to transfer from non-stateful block!");
-
-                            Some((source, self.game_state.player))
-                        }
-                        // TODO: Block -> Block?
+                        ) if source_block == target_block => self
+                            .game_state
+                            .world
+                            .block_states
+                            .get(source_block)
+                            .map(|source| (*source, self.game_state.player, *source_side)),
+                        // Block -> Block transfers are done by hoppers & conveyors, see `hopper_tick`
                         _ => {
                             // Nothing
                             None
                         }
                     } {
-                        MESSAGE_QUEUE.send(TransferItem(TransferItemMessage {
+                        // Terminals pass the items on to the chests on their network
+                        let message = TransferItemMessage {
                             source,
                             dest,
                             item: *item,
                             count: *count,
-                        }));
+                            source_side,
+                            dest_side: None,
+                        };
+                        route_transfer(&self.game_state.ecs, message)
+                            .into_iter()
+                            .for_each(|message| MESSAGE_QUEUE.send(TransferItem(message)));
                     }
                 }
                 &Message::ItemFavourited(ItemFavouritedMessage { item, slot }) => {
@@ -146,12 +158,21 @@ impl App {
                         .unwrap();
                     hotbar.set_favourite(slot, item);
                 }
+                Message::SetItemView(SetItemViewMessage { window, view }) => {
+                    let mut views = self
+                        .game_state
+                        .ecs
+                        .get::<&mut ItemViews>(self.game_state.player)
+                        .unwrap();
+                    views.0.insert(window.clone(), view.clone());
+                }
                 _ => (),
             }
 
             // Handle game state events first
             self.game_state.handle_message(&m);
             self.game_state.world.handle_message(&m);
+            self.game_state.paths.handle_message(&m);
 
             // Then player interaction events
             self.player_controller.handle_message(&m);
@@ -0,0 +1,65 @@
+/*
+Recipe tech-tree tool. Prints a summary of recipe costs & problems, and writes the graph out in
+DOT format if a path is given.
+Usage: cargo run --bin recipes -- [recipes.dot]
+*/
+
+use std::{env, fs};
+
+use enum_map::Enum;
+use game_engine::data::{
+    item::ItemType,
+    recipe::{
+        all_recipes,
+        analysis::{RecipeGraph, block_drops},
+    },
+};
+use itertools::Itertools;
+
+fn main() -> anyhow::Result<()> {
+    let recipes = all_recipes();
+    let graph = RecipeGraph::new(&recipes);
+
+    println!("Raw materials:");
+    graph
+        .raw_materials()
+        .iter()
+        .for_each(|item| println!("    {}", item.data().name));
+
+    println!("Crafted item costs:");
+    (0..ItemType::LENGTH)
+        .map(ItemType::from_usize)
+        .filter(|item| !graph.is_raw(*item))
+        .for_each(|item| match graph.cost(item) {
+            Some(cost) => {
+                let raw = cost
+                    .raw_materials
+                    .iter()
+                    .sorted_by_key(|(item, _)| item.into_usize())
+                    .map(|(item, count)| format!("{count} {}", item.data().name))
+                    .join(", ");
+                println!(
+                    "    {}: {raw} + {} juice",
+                    item.data().name,
+                    cost.crafting_juice
+                );
+            }
+            None => println!("    {}: only craftable through a cycle", item.data().name),
+        });
+
+    graph
+        .find_cycles()
+        .iter()
+        .for_each(|cycle| println!("Cycle: {cycle:?}"));
+    graph
+        .unreachable_items(&block_drops())
+        .iter()
+        .for_each(|item| println!("Unreachable: {item:?}"));
+
+    if let Some(path) = env::args().nth(1) {
+        fs::write(&path, graph.to_dot())?;
+        println!("Graph written to {path}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,484 @@
+/*
+Generic access to anything which holds items, so items move between blocks & entities the same way.
+Access is either through a side of a block, or direct (None) eg. through a UI.
+*/
+
+use std::fmt::Display;
+
+use cgmath::Vector3;
+use enum_map::Enum;
+
+use crate::{
+    data::{item::ItemType, recipe::smelting::SMELTING_RECIPES},
+    entity::components::{Conveyor, Crafter, Furnace, Generator, Inventory, ItemFilter},
+    item::ItemStack,
+    state::world::BlockPos,
+};
+
+/// Face of a block items move through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Enum)]
+pub enum Side {
+    Up,
+    Down,
+    North,
+    South,
+    East,
+    West,
+}
+
+impl Side {
+    pub const ALL: [Side; 6] = [
+        Side::Up,
+        Side::Down,
+        Side::North,
+        Side::South,
+        Side::East,
+        Side::West,
+    ];
+
+    /// Direction to the neighbouring block on this side
+    pub fn offset(&self) -> Vector3<i32> {
+        match self {
+            Side::Up => Vector3::unit_y(),
+            Side::Down => -Vector3::unit_y(),
+            Side::North => -Vector3::unit_z(),
+            Side::South => Vector3::unit_z(),
+            Side::East => Vector3::unit_x(),
+            Side::West => -Vector3::unit_x(),
+        }
+    }
+
+    pub fn opposite(&self) -> Side {
+        match self {
+            Side::Up => Side::Down,
+            Side::Down => Side::Up,
+            Side::North => Side::South,
+            Side::South => Side::North,
+            Side::East => Side::West,
+            Side::West => Side::East,
+        }
+    }
+
+    /// Horizontal side closest to a direction, eg. the way the player is looking
+    pub fn facing(direction: Vector3<f32>) -> Side {
+        if direction.x.abs() > direction.z.abs() {
+            if direction.x > 0. {
+                Side::East
+            } else {
+                Side::West
+            }
+        } else if direction.z > 0. {
+            Side::South
+        } else {
+            Side::North
+        }
+    }
+
+    /// Side of `from` which faces `to`. None unless the blocks are touching.
+    pub fn between(from: &BlockPos, to: &BlockPos) -> Option<Side> {
+        let diff = to.0 - from.0;
+        Side::ALL.into_iter().find(|side| side.offset() == diff)
+    }
+}
+
+/// Why no items could be moved between two containers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferError {
+    /// One of the entities is gone, or doesn't hold items
+    NoContainer,
+    /// The source has none of the item to give out through that side
+    NothingToMove,
+    /// The destination has no room for the item through that side
+    NoSpace,
+}
+
+impl Display for TransferError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransferError::NoContainer => write!(f, "Nothing to move it to"),
+            TransferError::NothingToMove => write!(f, "None left to move"),
+            TransferError::NoSpace => write!(f, "No room for it"),
+        }
+    }
+}
+
+/// Anything which holds items
+pub trait Container {
+    /// Inventory items put in through a side end up in. None if the side doesn't take items.
+    fn input(&mut self, side: Option<Side>) -> Option<&mut Inventory>;
+
+    /// Inventory items taken out through a side come from. None if the side doesn't give items.
+    fn output(&mut self, side: Option<Side>) -> Option<&mut Inventory>;
+
+    /// Side an item taken out through a side really comes from. Direct access can pick between
+    /// inventories by what they hold, this pins the choice down so it holds after taking items.
+    fn output_side(&self, _item: ItemType, side: Option<Side>) -> Option<Side> {
+        side
+    }
+
+    /// How many of an item can be put in through a side
+    fn space_for_side(&mut self, item: ItemType, side: Option<Side>) -> usize {
+        self.input(side)
+            .map_or(0, |inventory| inventory.space_for(item))
+    }
+
+    /// Put items in through a side, giving back whatever didn't fit.
+    /// Nothing changes when simulating.
+    fn insert(
+        &mut self,
+        stack: ItemStack,
+        side: Option<Side>,
+        simulate: bool,
+    ) -> Option<ItemStack> {
+        let space = self.space_for_side(stack.item_type(), side);
+        let Some(inventory) = self.input(side) else {
+            return Some(stack);
+        };
+
+        match stack {
+            ItemStack::Counted(item, count) => {
+                let fits = count.min(space);
+                if !simulate && fits > 0 {
+                    inventory.insert_stack(ItemStack::Counted(item, fits));
+                }
+
+                (fits < count).then_some(ItemStack::Counted(item, count - fits))
+            }
+            ItemStack::Unique(_) if space == 0 => Some(stack),
+            ItemStack::Unique(_) => {
+                if !simulate {
+                    inventory.insert_stack(stack);
+                }
+
+                None
+            }
+        }
+    }
+
+    /// Take up to `count` of an item out through a side. Nothing changes when simulating.
+    fn extract(
+        &mut self,
+        item: ItemType,
+        count: usize,
+        side: Option<Side>,
+        simulate: bool,
+    ) -> Vec<ItemStack> {
+        let side = self.output_side(item, side);
+        let Some(inventory) = self.output(side) else {
+            return vec![];
+        };
+
+        let count = count.min(inventory.items[item]);
+        if count == 0 {
+            return vec![];
+        }
+
+        if simulate {
+            inventory.peek_items(item, count)
+        } else {
+            inventory.take_items(item, count)
+        }
+    }
+}
+
+/// Chests & the player's inventory are one big store, open from every side
+impl Container for Inventory {
+    fn input(&mut self, _side: Option<Side>) -> Option<&mut Inventory> {
+        Some(self)
+    }
+
+    fn output(&mut self, _side: Option<Side>) -> Option<&mut Inventory> {
+        Some(self)
+    }
+}
+
+/// Chest with rules on what it takes in
+pub struct Filtered<'a> {
+    pub inventory: &'a mut Inventory,
+    pub filter: &'a ItemFilter,
+}
+
+impl Container for Filtered<'_> {
+    fn input(&mut self, _side: Option<Side>) -> Option<&mut Inventory> {
+        Some(self.inventory)
+    }
+
+    fn output(&mut self, _side: Option<Side>) -> Option<&mut Inventory> {
+        Some(self.inventory)
+    }
+
+    fn space_for_side(&mut self, item: ItemType, _side: Option<Side>) -> usize {
+        match self.filter.accepts(item) {
+            true => self.inventory.space_for(item),
+            false => 0,
+        }
+    }
+}
+
+/// Crafters take ingredients in from the top & sides and give products out the bottom.
+/// Ingredients can be taken back out the top.
+impl Container for Crafter {
+    fn input(&mut self, side: Option<Side>) -> Option<&mut Inventory> {
+        match side {
+            Some(Side::Down) => None,
+            _ => Some(&mut self.input),
+        }
+    }
+
+    fn output(&mut self, side: Option<Side>) -> Option<&mut Inventory> {
+        match side {
+            None | Some(Side::Down) => Some(&mut self.output),
+            Some(Side::Up) => Some(&mut self.input),
+            _ => None,
+        }
+    }
+
+    fn output_side(&self, item: ItemType, side: Option<Side>) -> Option<Side> {
+        // Direct access can take ingredients back out too
+        match side {
+            None if self.output.items[item] == 0 => Some(Side::Up),
+            None => Some(Side::Down),
+            side => side,
+        }
+    }
+}
+
+/// Conveyors take items in anywhere but the end they feed out of.
+/// Items already on the belt are entities, so only the waiting ones can be taken back.
+impl Container for Conveyor {
+    fn input(&mut self, side: Option<Side>) -> Option<&mut Inventory> {
+        (side != Some(self.direction)).then_some(&mut self.input)
+    }
+
+    fn output(&mut self, side: Option<Side>) -> Option<&mut Inventory> {
+        side.is_none().then_some(&mut self.input)
+    }
+}
+
+/// Generators only take fuel, from any side
+impl Container for Generator {
+    fn input(&mut self, _side: Option<Side>) -> Option<&mut Inventory> {
+        self.burner.as_mut().map(|burner| &mut burner.fuel)
+    }
+
+    fn output(&mut self, side: Option<Side>) -> Option<&mut Inventory> {
+        self.burner
+            .as_mut()
+            .filter(|_| side.is_none())
+            .map(|burner| &mut burner.fuel)
+    }
+
+    fn space_for_side(&mut self, item: ItemType, side: Option<Side>) -> usize {
+        if item.data().burn_value.is_none() {
+            return 0;
+        }
+
+        self.input(side)
+            .map_or(0, |inventory| inventory.space_for(item))
+    }
+}
+
+impl Furnace {
+    /// Where an item put in through a side ends up. Narrows down `input` by the item.
+    fn slot_for(&mut self, item: ItemType, side: Option<Side>) -> Option<&mut Inventory> {
+        let burnable = item.data().burn_value.is_some();
+
+        match side {
+            // Direct access tops up the fuel first, unless the ingredients need it
+            None if burnable
+                && self.burner.fuel.space_for(item) > 0
+                && !self.wants_ingredient(item) =>
+            {
+                Some(&mut self.burner.fuel)
+            }
+            // Only fuel goes in the sides
+            Some(Side::North | Side::South | Side::East | Side::West) if !burnable => None,
+            _ => self.input(side),
+        }
+    }
+
+    /// Whether the input is short of an item to cook what else it holds, eg. coal to go with iron
+    fn wants_ingredient(&self, item: ItemType) -> bool {
+        SMELTING_RECIPES.iter().any(|recipe| {
+            let Some(needed) = recipe.inputs.get(&item) else {
+                return false;
+            };
+            let batches = recipe
+                .inputs
+                .iter()
+                .filter(|(other, _)| **other != item)
+                .map(|(other, count)| self.input.items[*other] / count)
+                .min()
+                .unwrap_or(0);
+            self.input.items[item] < batches * needed
+        })
+    }
+}
+
+/// Furnaces take ingredients in the top, fuel in the sides, and give products out the bottom.
+/// Ingredients & fuel can be taken back out the way they came in.
+impl Container for Furnace {
+    fn input(&mut self, side: Option<Side>) -> Option<&mut Inventory> {
+        match side {
+            None | Some(Side::Up) => Some(&mut self.input),
+            Some(Side::Down) => None,
+            Some(Side::North | Side::South | Side::East | Side::West) => {
+                Some(&mut self.burner.fuel)
+            }
+        }
+    }
+
+    fn output(&mut self, side: Option<Side>) -> Option<&mut Inventory> {
+        match side {
+            None | Some(Side::Down) => Some(&mut self.output),
+            Some(Side::Up) => Some(&mut self.input),
+            Some(Side::North | Side::South | Side::East | Side::West) => {
+                Some(&mut self.burner.fuel)
+            }
+        }
+    }
+
+    fn space_for_side(&mut self, item: ItemType, side: Option<Side>) -> usize {
+        self.slot_for(item, side)
+            .map_or(0, |inventory| inventory.space_for(item))
+    }
+
+    fn insert(
+        &mut self,
+        stack: ItemStack,
+        side: Option<Side>,
+        simulate: bool,
+    ) -> Option<ItemStack> {
+        match self.slot_for(stack.item_type(), side) {
+            Some(inventory) => inventory.insert(stack, None, simulate),
+            None => Some(stack),
+        }
+    }
+
+    fn output_side(&self, item: ItemType, side: Option<Side>) -> Option<Side> {
+        // Direct access can take ingredients & fuel back out too
+        match side {
+            None if self.output.items[item] > 0 => Some(Side::Down),
+            None if self.input.items[item] > 0 => Some(Side::Up),
+            None => Some(Side::North),
+            side => side,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        container::{Container, Side},
+        data::item::ItemType,
+        entity::components::{Capacity, Crafter, Furnace, Inventory},
+        item::ItemStack,
+        state::world::BlockPos,
+    };
+
+    #[test]
+    fn test_simulate() {
+        let mut chest = Inventory::with_capacity(Capacity {
+            max_slots: Some(1),
+            max_stack: Some(10),
+            ..Default::default()
+        });
+
+        // Simulating reports the overflow without changing anything
+        let leftover = chest.insert(ItemStack::Counted(ItemType::Stone, 15), None, true);
+        assert_eq!(leftover, Some(ItemStack::Counted(ItemType::Stone, 5)));
+        assert_eq!(chest.items[ItemType::Stone], 0);
+
+        chest.insert(ItemStack::Counted(ItemType::Stone, 15), None, false);
+        assert_eq!(chest.items[ItemType::Stone], 10);
+
+        let taken = chest.extract(ItemType::Stone, 20, Some(Side::Up), true);
+        assert_eq!(taken, vec![ItemStack::Counted(ItemType::Stone, 10)]);
+        assert_eq!(chest.items[ItemType::Stone], 10);
+    }
+
+    #[test]
+    fn test_furnace_slots() {
+        let mut furnace = Furnace::default();
+        let coal = || ItemStack::Counted(ItemType::Coal, 2);
+
+        // Coal goes in as fuel, until there's iron waiting for it
+        assert!(furnace.insert(coal(), None, false).is_none());
+        assert_eq!(furnace.burner.fuel.items[ItemType::Coal], 2);
+        furnace.insert(ItemStack::Counted(ItemType::Iron, 3), None, false);
+        assert!(furnace.insert(coal(), None, false).is_none());
+        assert_eq!(furnace.input.items[ItemType::Coal], 2);
+        furnace.insert(ItemStack::Counted(ItemType::Coal, 1), None, false);
+        assert_eq!(furnace.input.items[ItemType::Coal], 3);
+        assert!(furnace.insert(coal(), None, false).is_none());
+        assert_eq!(furnace.burner.fuel.items[ItemType::Coal], 4);
+
+        // Sides only take fuel
+        assert_eq!(furnace.space_for_side(ItemType::Iron, Some(Side::East)), 0);
+        assert!(furnace.space_for_side(ItemType::Coal, Some(Side::East)) > 0);
+        assert!(furnace.space_for_side(ItemType::Iron, Some(Side::Up)) > 0);
+
+        // Each side gives back what it takes in
+        furnace.extract(ItemType::Coal, 1, Some(Side::East), false);
+        assert_eq!(furnace.burner.fuel.items[ItemType::Coal], 3);
+        assert_eq!(furnace.input.items[ItemType::Coal], 3);
+        furnace.extract(ItemType::Coal, 1, Some(Side::Up), false);
+        assert_eq!(furnace.input.items[ItemType::Coal], 2);
+    }
+
+    #[test]
+    fn test_crafter_sides() {
+        let mut crafter = Crafter::default();
+
+        // Ingredients go in the input buffer, but not from below
+        assert!(
+            crafter
+                .insert(
+                    ItemStack::Counted(ItemType::Copper, 2),
+                    Some(Side::North),
+                    false
+                )
+                .is_none()
+        );
+        assert!(
+            crafter
+                .insert(
+                    ItemStack::Counted(ItemType::Copper, 2),
+                    Some(Side::Down),
+                    false
+                )
+                .is_some()
+        );
+        assert_eq!(crafter.input.items[ItemType::Copper], 2);
+
+        // Ingredients can't be pulled out from the sides
+        assert!(
+            crafter
+                .extract(ItemType::Copper, 1, Some(Side::Down), false)
+                .is_empty()
+        );
+        assert!(
+            crafter
+                .extract(ItemType::Copper, 1, Some(Side::East), false)
+                .is_empty()
+        );
+        assert_eq!(crafter.extract(ItemType::Copper, 1, None, false).len(), 1);
+
+        // With the same item in both buffers, each side only drains its own
+        crafter.output.add_item(ItemType::Copper, 3);
+        crafter.extract(ItemType::Copper, 1, Some(Side::Up), false);
+        assert_eq!(crafter.input.items[ItemType::Copper], 0);
+        assert_eq!(crafter.output.items[ItemType::Copper], 3);
+        crafter.extract(ItemType::Copper, 1, Some(Side::Down), false);
+        assert_eq!(crafter.output.items[ItemType::Copper], 2);
+
+        assert_eq!(
+            Side::between(&BlockPos::new(0, 0, 0), &BlockPos::new(0, -1, 0)),
+            Some(Side::Down)
+        );
+        assert_eq!(
+            Side::between(&BlockPos::new(0, 0, 0), &BlockPos::new(0, -2, 0)),
+            None
+        );
+    }
+}
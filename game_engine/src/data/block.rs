@@ -1,13 +1,19 @@
 use std::sync::LazyLock;
 
-use enum_map::Enum;
+use enum_map::{Enum, EnumMap};
 use hecs::EntityBuilder;
 use num_derive::{FromPrimitive, ToPrimitive};
 use typed_builder::TypedBuilder;
 
 use crate::{
-    data::item::ItemType,
-    entity::components::{Container, Crafter, UIType},
+    data::{
+        item::{ItemType, ToolKind},
+        recipe::RecipeCategory,
+    },
+    entity::components::{
+        Capacity, ChunkLoader, Conveyor, Crafter, Furnace, Generator, Hopper, Inventory,
+        ItemFilter, PowerNode, StorageNode, StorageTerminal, Ticker, UIType,
+    },
 };
 
 #[derive(Debug, Enum, PartialEq, Eq, Clone, Copy, ToPrimitive, FromPrimitive)]
@@ -28,6 +34,16 @@ pub enum BlockType {
     // Interactables
     Chest,
     Crafter,
+    Hopper,
+    Conveyor,
+    Generator,
+    Reactor,
+    Conduit,
+    Furnace,
+    ChunkLoader,
+    StorageTerminal,
+    Crusher,
+    Washer,
 }
 
 #[derive(TypedBuilder, Debug, Clone)]
@@ -39,6 +55,10 @@ pub struct BlockData {
     #[builder(default, setter(strip_option))]
     pub hardness: Option<u32>,
 
+    /// None == any tool works, otherwise other tools are slow & don't get the item
+    #[builder(default, setter(strip_option))]
+    pub preferred_tool: Option<ToolKind>,
+
     #[builder(default, setter(strip_option))]
     pub item_on_break: Option<ItemType>,
 
@@ -53,6 +73,13 @@ pub struct BlockData {
     pub state: Option<fn(&mut EntityBuilder)>,
 }
 
+impl BlockType {
+    /// Get the user-defined data for this block
+    pub fn data(&self) -> &'static BlockData {
+        BLOCK_DATA_BY_TYPE[*self]
+    }
+}
+
 pub(super) const TEXTURE_FOLDER: &str = "res/meshes";
 pub(super) static BLOCK_DATA: LazyLock<Vec<BlockData>> = LazyLock::new(|| {
     vec![
@@ -66,36 +93,42 @@ pub(super) static BLOCK_DATA: LazyLock<Vec<BlockData>> = LazyLock::new(|| {
             .texture_path("dirt.png")
             .block_type(BlockType::Dirt)
             .hardness(0)
+            .preferred_tool(ToolKind::Shovel)
             .item_on_break(ItemType::Dirt)
             .build(),
         BlockData::builder()
             .texture_path("stone.png")
             .block_type(BlockType::Stone)
             .hardness(10)
+            .preferred_tool(ToolKind::Pickaxe)
             .item_on_break(ItemType::Stone)
             .build(),
         BlockData::builder()
             .texture_path("darkstone.png")
             .block_type(BlockType::DarkStone)
             .hardness(100)
+            .preferred_tool(ToolKind::Pickaxe)
             .item_on_break(ItemType::DarkStone)
             .build(),
         BlockData::builder()
             .texture_path("mossystone.png")
             .block_type(BlockType::MossyStone)
             .hardness(10)
+            .preferred_tool(ToolKind::Pickaxe)
             .item_on_break(ItemType::MossyStone)
             .build(),
         BlockData::builder()
             .texture_path("voidstone.png")
             .block_type(BlockType::VoidStone)
             .hardness(200)
+            .preferred_tool(ToolKind::Pickaxe)
             .item_on_break(ItemType::VoidStone)
             .build(),
         BlockData::builder()
             .texture_path("radioactivestone.png")
             .block_type(BlockType::RadioactiveStone)
             .hardness(200)
+            .preferred_tool(ToolKind::Pickaxe)
             .item_on_break(ItemType::RadioactiveStone)
             .build(),
         // Ores
@@ -103,57 +136,220 @@ pub(super) static BLOCK_DATA: LazyLock<Vec<BlockData>> = LazyLock::new(|| {
             .texture_path("copper.png")
             .block_type(BlockType::Copper)
             .hardness(100)
+            .preferred_tool(ToolKind::Pickaxe)
             .item_on_break(ItemType::Copper)
             .build(),
         BlockData::builder()
             .texture_path("tin.png")
             .block_type(BlockType::Tin)
             .hardness(200)
+            .preferred_tool(ToolKind::Pickaxe)
             .item_on_break(ItemType::Tin)
             .build(),
         BlockData::builder()
             .texture_path("iron.png")
             .block_type(BlockType::Iron)
             .hardness(300)
+            .preferred_tool(ToolKind::Pickaxe)
             .item_on_break(ItemType::Iron)
             .build(),
         BlockData::builder()
             .texture_path("coal.png")
             .block_type(BlockType::Coal)
             .hardness(300)
+            .preferred_tool(ToolKind::Pickaxe)
             .item_on_break(ItemType::Coal)
             .build(),
         BlockData::builder()
             .texture_path("magic_metal.png")
             .block_type(BlockType::MagicMetal)
             .hardness(400)
+            .preferred_tool(ToolKind::Pickaxe)
             .item_on_break(ItemType::MagicMetal)
             .build(),
         BlockData::builder()
             .texture_path("chest.png")
             .block_type(BlockType::Chest)
             .hardness(0)
+            .preferred_tool(ToolKind::Axe)
             .item_on_break(ItemType::Chest)
             .interactable(true)
             .state(|builder| {
-                builder.add(Container::default()).add(UIType::Chest);
+                builder
+                    .add(Inventory::with_capacity(Capacity {
+                        max_slots: Some(27),
+                        max_stack: Some(64),
+                        ..Default::default()
+                    }))
+                    .add(UIType::Chest)
+                    .add(ItemFilter::default())
+                    .add(StorageNode);
             })
             .build(),
         BlockData::builder()
             .texture_path("crafter.png")
             .block_type(BlockType::Crafter)
             .hardness(0)
+            .preferred_tool(ToolKind::Axe)
             .item_on_break(ItemType::Crafter)
             .interactable(true)
+            .state(|builder| {
+                machine_state(builder, RecipeCategory::Crafting);
+            })
+            .build(),
+        BlockData::builder()
+            .texture_path("hopper.png")
+            .block_type(BlockType::Hopper)
+            .hardness(10)
+            .preferred_tool(ToolKind::Pickaxe)
+            .item_on_break(ItemType::Hopper)
+            .interactable(true)
+            .state(|builder| {
+                builder.add(UIType::Hopper).add(Hopper::default());
+            })
+            .build(),
+        BlockData::builder()
+            .texture_path("conveyor.png")
+            .block_type(BlockType::Conveyor)
+            .hardness(10)
+            .preferred_tool(ToolKind::Pickaxe)
+            .item_on_break(ItemType::Conveyor)
+            .interactable(true)
+            .state(|builder| {
+                builder.add(UIType::Conveyor).add(Conveyor::default());
+            })
+            .build(),
+        BlockData::builder()
+            .texture_path("generator.png")
+            .block_type(BlockType::Generator)
+            .hardness(20)
+            .preferred_tool(ToolKind::Pickaxe)
+            .item_on_break(ItemType::Generator)
+            .interactable(true)
             .state(|builder| {
                 builder
-                    .add(Container::default())
-                    .add(UIType::Crafter)
-                    .add(Crafter {
-                        juice_per_second: 1.,
-                        ..Default::default()
-                    });
+                    .add(UIType::Generator)
+                    .add(Generator::burner(3.))
+                    .add(PowerNode::default())
+                    .add(Ticker::every(Generator::TICK_INTERVAL));
+            })
+            .build(),
+        BlockData::builder()
+            .texture_path("reactor.png")
+            .block_type(BlockType::Reactor)
+            .hardness(200)
+            .preferred_tool(ToolKind::Pickaxe)
+            .item_on_break(ItemType::Reactor)
+            .interactable(true)
+            .state(|builder| {
+                builder
+                    .add(UIType::Generator)
+                    .add(Generator::passive(1.))
+                    .add(PowerNode::default())
+                    .add(Ticker::every(Generator::TICK_INTERVAL));
+            })
+            .build(),
+        BlockData::builder()
+            .texture_path("conduit.png")
+            .block_type(BlockType::Conduit)
+            .hardness(5)
+            .preferred_tool(ToolKind::Pickaxe)
+            .item_on_break(ItemType::Conduit)
+            .interactable(true)
+            .state(|builder| {
+                builder.add(UIType::Conduit).add(PowerNode::default());
+            })
+            .build(),
+        BlockData::builder()
+            .texture_path("furnace.png")
+            .block_type(BlockType::Furnace)
+            .hardness(20)
+            .preferred_tool(ToolKind::Pickaxe)
+            .item_on_break(ItemType::Furnace)
+            .interactable(true)
+            .state(|builder| {
+                builder
+                    .add(UIType::Furnace)
+                    .add(Furnace::default())
+                    .add(Ticker::every(Furnace::TICK_INTERVAL));
+            })
+            .build(),
+        BlockData::builder()
+            .texture_path("chunk_loader.png")
+            .block_type(BlockType::ChunkLoader)
+            .hardness(50)
+            .preferred_tool(ToolKind::Pickaxe)
+            .item_on_break(ItemType::ChunkLoader)
+            .interactable(true)
+            .state(|builder| {
+                builder.add(UIType::ChunkLoader).add(ChunkLoader::default());
+            })
+            .build(),
+        BlockData::builder()
+            .texture_path("storage_terminal.png")
+            .block_type(BlockType::StorageTerminal)
+            .hardness(10)
+            .preferred_tool(ToolKind::Pickaxe)
+            .item_on_break(ItemType::StorageTerminal)
+            .interactable(true)
+            .state(|builder| {
+                builder
+                    .add(UIType::StorageTerminal)
+                    .add(StorageTerminal::default())
+                    .add(StorageNode);
+            })
+            .build(),
+        BlockData::builder()
+            .texture_path("crusher.png")
+            .block_type(BlockType::Crusher)
+            .hardness(20)
+            .preferred_tool(ToolKind::Pickaxe)
+            .item_on_break(ItemType::Crusher)
+            .interactable(true)
+            .state(|builder| {
+                machine_state(builder, RecipeCategory::Crushing);
+            })
+            .build(),
+        BlockData::builder()
+            .texture_path("washer.png")
+            .block_type(BlockType::Washer)
+            .hardness(20)
+            .preferred_tool(ToolKind::Pickaxe)
+            .item_on_break(ItemType::Washer)
+            .interactable(true)
+            .state(|builder| {
+                machine_state(builder, RecipeCategory::Washing);
             })
             .build(),
     ]
 });
+
+/// `BLOCK_DATA` looked up by block type, built once
+static BLOCK_DATA_BY_TYPE: LazyLock<EnumMap<BlockType, &'static BlockData>> = LazyLock::new(|| {
+    EnumMap::from_fn(|k: BlockType| {
+        BLOCK_DATA
+            .iter()
+            .find(|d| d.block_type == k)
+            .unwrap_or_else(|| panic!("User-defined BlockData not found for: {k:?}"))
+    })
+});
+
+/// Crafters & the other machines which run recipes on crafting juice
+fn machine_state(builder: &mut EntityBuilder, category: RecipeCategory) {
+    let buffer_capacity = Capacity {
+        max_slots: Some(9),
+        max_stack: Some(64),
+        ..Default::default()
+    };
+    builder
+        .add(UIType::Crafter)
+        .add(Crafter {
+            category,
+            juice_per_second: 1.,
+            input: Inventory::with_capacity(buffer_capacity.clone()),
+            output: Inventory::with_capacity(buffer_capacity),
+            ..Default::default()
+        })
+        .add(PowerNode::default())
+        .add(Ticker::every(Crafter::TICK_INTERVAL));
+}
@@ -0,0 +1,78 @@
+use std::sync::LazyLock;
+
+use cgmath::Point3;
+use enum_map::{Enum, EnumMap};
+use hecs::EntityBuilder;
+use typed_builder::TypedBuilder;
+
+use crate::{entity::components::Behaviour, math::bbox::AABB};
+
+#[derive(Debug, Enum, PartialEq, Eq, Clone, Copy)]
+pub enum EntityType {
+    Sibeal,
+}
+
+#[derive(TypedBuilder, Debug, Clone)]
+pub struct EntityData {
+    /// Relative to the mesh folder
+    pub(super) model_path: &'static str,
+    pub entity_type: EntityType,
+
+    /// Model coordinates, relative to the entity's position
+    pub hitbox: AABB<f32>,
+
+    pub health: f32,
+
+    /// Blocks per second
+    #[builder(default)]
+    pub speed: f32,
+
+    /// How far away it can see the player from
+    #[builder(default = 16.)]
+    pub vision: f32,
+
+    /// What it gets up to when first spawned
+    #[builder(default = Behaviour::Idle(0.))]
+    pub behaviour: Behaviour,
+
+    /// Anything extra on top of the usual components, eg. an inventory
+    #[builder(default, setter(strip_option))]
+    pub state: Option<fn(&mut EntityBuilder)>,
+}
+
+impl EntityType {
+    /// Get the user-defined data for this entity
+    pub fn data(&self) -> &'static EntityData {
+        ENTITY_DATA_BY_TYPE[*self]
+    }
+}
+
+pub(super) const MODEL_FOLDER: &str = "res/meshes";
+
+pub(super) static ENTITY_DATA: LazyLock<Vec<EntityData>> = LazyLock::new(|| {
+    vec![
+        EntityData::builder()
+            .model_path("sibeal.obj")
+            .entity_type(EntityType::Sibeal)
+            .hitbox(AABB::new(
+                &Point3::new(-0.5, 0., -0.5),
+                &Point3::new(0.5, 1., 0.5),
+            ))
+            .health(20.)
+            .speed(1.5)
+            .vision(12.)
+            .behaviour(Behaviour::Idle(0.))
+            .build(),
+    ]
+});
+
+/// `ENTITY_DATA` looked up by entity type, built once
+static ENTITY_DATA_BY_TYPE: LazyLock<EnumMap<EntityType, &'static EntityData>> =
+    LazyLock::new(|| {
+        EnumMap::from_fn(|k: EntityType| {
+            ENTITY_DATA
+                .iter()
+                .find(|d| d.entity_type == k)
+                .unwrap_or_else(|| panic!("User-defined EntityData not found for: {k:?}"))
+        })
+    });
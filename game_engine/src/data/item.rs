@@ -1,6 +1,6 @@
 use std::sync::LazyLock;
 
-use enum_map::Enum;
+use enum_map::{Enum, EnumMap};
 use typed_builder::TypedBuilder;
 
 use crate::data::block::BlockType;
@@ -21,15 +21,79 @@ pub enum ItemType {
     Iron,
     Steel,
     MagicMetal,
+    // Processed ores
+    CopperDust,
+    TinDust,
+    IronDust,
+    // Byproducts
+    Slag,
     // Tools
     CopperPickaxe,
     BronzePickaxe,
     IronPickaxe,
     SteelPickaxe,
     MagicMetalPickaxe,
+    CopperShovel,
+    CopperAxe,
+    MagicMetalDrill,
     // Interactable blocks
     Chest,
     Crafter,
+    Hopper,
+    Conveyor,
+    Generator,
+    Reactor,
+    Conduit,
+    Furnace,
+    ChunkLoader,
+    StorageTerminal,
+    Crusher,
+    Washer,
+}
+
+/// Kinds of tool, blocks are mined best by a particular kind
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ToolKind {
+    Pickaxe,
+    Shovel,
+    Axe,
+    /// Does the job of both a pickaxe & a shovel
+    Drill,
+}
+
+impl ToolKind {
+    /// Whether this tool counts as the given kind of tool
+    pub fn is_effective_as(&self, kind: ToolKind) -> bool {
+        *self == kind
+            || (*self == ToolKind::Drill && matches!(kind, ToolKind::Pickaxe | ToolKind::Shovel))
+    }
+}
+
+/// Broad groups of items, used to filter item lists
+#[derive(Enum, Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum ItemCategory {
+    Blocks,
+    Materials,
+    Tools,
+    Machines,
+}
+
+impl ItemCategory {
+    pub const ALL: [ItemCategory; 4] = [
+        ItemCategory::Blocks,
+        ItemCategory::Materials,
+        ItemCategory::Tools,
+        ItemCategory::Machines,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ItemCategory::Blocks => "Blocks",
+            ItemCategory::Materials => "Materials",
+            ItemCategory::Tools => "Tools",
+            ItemCategory::Machines => "Machines",
+        }
+    }
 }
 
 // User-defined
@@ -47,6 +111,41 @@ pub struct ItemData {
 
     #[builder(default, setter(strip_option))]
     pub breaking_strength: Option<u32>,
+
+    #[builder(default, setter(strip_option))]
+    pub tool: Option<ToolKind>,
+
+    /// None == stackable, otherwise each item is unique and wears down with use
+    #[builder(default, setter(strip_option))]
+    pub durability: Option<u32>,
+
+    /// Seconds the item burns for as fuel. None == not a fuel
+    #[builder(default, setter(strip_option))]
+    pub burn_value: Option<f32>,
+}
+
+impl ItemType {
+    /// Get the user-defined data for this item
+    pub fn data(&self) -> &'static ItemData {
+        ITEM_DATA_BY_TYPE[*self]
+    }
+
+    /// Group the item falls under, worked out from what it does
+    pub fn category(&self) -> ItemCategory {
+        let data = self.data();
+        match data.block {
+            _ if data.tool.is_some() => ItemCategory::Tools,
+            Some(block) if block.data().interactable => ItemCategory::Machines,
+            Some(_) => ItemCategory::Blocks,
+            None => ItemCategory::Materials,
+        }
+    }
+
+    /// Whether multiple of this item can be held as a single count
+    #[inline]
+    pub fn is_stackable(&self) -> bool {
+        self.data().durability.is_none()
+    }
 }
 
 pub(super) const ICON_PATH: &str = "res/icons";
@@ -121,6 +220,7 @@ pub(super) static ITEM_DATA: LazyLock<Vec<ItemData>> = LazyLock::new(|| {
             .icon_path("coal.png")
             .weight(1.)
             .block(BlockType::Coal)
+            .burn_value(10.)
             .build(),
         ItemData::builder()
             .item_type(ItemType::Iron)
@@ -142,41 +242,104 @@ pub(super) static ITEM_DATA: LazyLock<Vec<ItemData>> = LazyLock::new(|| {
             .weight(1.)
             .block(BlockType::MagicMetal)
             .build(),
+        // Processed ores
+        ItemData::builder()
+            .item_type(ItemType::CopperDust)
+            .name("Copper Dust")
+            .icon_path("copper_dust.png")
+            .weight(0.5)
+            .build(),
+        ItemData::builder()
+            .item_type(ItemType::TinDust)
+            .name("Tin Dust")
+            .icon_path("tin_dust.png")
+            .weight(0.5)
+            .build(),
+        ItemData::builder()
+            .item_type(ItemType::IronDust)
+            .name("Iron Dust")
+            .icon_path("iron_dust.png")
+            .weight(0.5)
+            .build(),
+        // Byproducts
+        ItemData::builder()
+            .item_type(ItemType::Slag)
+            .name("Slag")
+            .icon_path("slag.png")
+            .weight(1.)
+            .build(),
         // Tools
         ItemData::builder()
             .item_type(ItemType::CopperPickaxe)
             .name("Copper Pickaxe")
             .icon_path("copper_pickaxe.png")
             .weight(1.)
+            .tool(ToolKind::Pickaxe)
             .breaking_strength(100)
+            .durability(50)
             .build(),
         ItemData::builder()
             .item_type(ItemType::BronzePickaxe)
             .name("Bronze Pickaxe")
             .icon_path("bronze_pickaxe.png")
             .weight(1.)
+            .tool(ToolKind::Pickaxe)
             .breaking_strength(200)
+            .durability(100)
             .build(),
         ItemData::builder()
             .item_type(ItemType::IronPickaxe)
             .name("Iron Pickaxe")
             .icon_path("iron_pickaxe.png")
             .weight(1.)
+            .tool(ToolKind::Pickaxe)
             .breaking_strength(300)
+            .durability(200)
             .build(),
         ItemData::builder()
             .item_type(ItemType::SteelPickaxe)
             .name("Steel Pickaxe")
             .icon_path("steel_pickaxe.png")
             .weight(1.)
+            .tool(ToolKind::Pickaxe)
             .breaking_strength(400)
+            .durability(400)
             .build(),
         ItemData::builder()
             .item_type(ItemType::MagicMetalPickaxe)
             .name("Magic Metal Pickaxe")
             .icon_path("magic_metal_pickaxe.png")
             .weight(1.)
+            .tool(ToolKind::Pickaxe)
             .breaking_strength(500)
+            .durability(1000)
+            .build(),
+        ItemData::builder()
+            .item_type(ItemType::CopperShovel)
+            .name("Copper Shovel")
+            .icon_path("copper_shovel.png")
+            .weight(1.)
+            .tool(ToolKind::Shovel)
+            .breaking_strength(100)
+            .durability(50)
+            .build(),
+        ItemData::builder()
+            .item_type(ItemType::CopperAxe)
+            .name("Copper Axe")
+            .icon_path("copper_axe.png")
+            .weight(1.)
+            .tool(ToolKind::Axe)
+            .breaking_strength(100)
+            .durability(50)
+            .build(),
+        ItemData::builder()
+            .item_type(ItemType::MagicMetalDrill)
+            .name("Magic Metal Drill")
+            .icon_path("magic_metal_drill.png")
+            .weight(2.)
+            .tool(ToolKind::Drill)
+            .breaking_strength(500)
+            .durability(2000)
             .build(),
         ItemData::builder()
             .item_type(ItemType::Chest)
@@ -192,5 +355,85 @@ pub(super) static ITEM_DATA: LazyLock<Vec<ItemData>> = LazyLock::new(|| {
             .weight(1.)
             .block(BlockType::Crafter)
             .build(),
+        ItemData::builder()
+            .item_type(ItemType::Hopper)
+            .name("Hopper")
+            .icon_path("hopper.png")
+            .weight(2.)
+            .block(BlockType::Hopper)
+            .build(),
+        ItemData::builder()
+            .item_type(ItemType::Conveyor)
+            .name("Conveyor")
+            .icon_path("conveyor.png")
+            .weight(1.)
+            .block(BlockType::Conveyor)
+            .build(),
+        ItemData::builder()
+            .item_type(ItemType::Generator)
+            .name("Generator")
+            .icon_path("generator.png")
+            .weight(4.)
+            .block(BlockType::Generator)
+            .build(),
+        ItemData::builder()
+            .item_type(ItemType::Reactor)
+            .name("Reactor")
+            .icon_path("reactor.png")
+            .weight(6.)
+            .block(BlockType::Reactor)
+            .build(),
+        ItemData::builder()
+            .item_type(ItemType::Conduit)
+            .name("Conduit")
+            .icon_path("conduit.png")
+            .weight(0.5)
+            .block(BlockType::Conduit)
+            .build(),
+        ItemData::builder()
+            .item_type(ItemType::Furnace)
+            .name("Furnace")
+            .icon_path("furnace.png")
+            .weight(4.)
+            .block(BlockType::Furnace)
+            .build(),
+        ItemData::builder()
+            .item_type(ItemType::ChunkLoader)
+            .name("Chunk Loader")
+            .icon_path("chunk_loader.png")
+            .weight(8.)
+            .block(BlockType::ChunkLoader)
+            .build(),
+        ItemData::builder()
+            .item_type(ItemType::StorageTerminal)
+            .name("Storage Terminal")
+            .icon_path("storage_terminal.png")
+            .weight(2.)
+            .block(BlockType::StorageTerminal)
+            .build(),
+        ItemData::builder()
+            .item_type(ItemType::Crusher)
+            .name("Crusher")
+            .icon_path("crusher.png")
+            .weight(4.)
+            .block(BlockType::Crusher)
+            .build(),
+        ItemData::builder()
+            .item_type(ItemType::Washer)
+            .name("Washer")
+            .icon_path("washer.png")
+            .weight(4.)
+            .block(BlockType::Washer)
+            .build(),
     ]
 });
+
+/// `ITEM_DATA` looked up by item type, built once
+static ITEM_DATA_BY_TYPE: LazyLock<EnumMap<ItemType, &'static ItemData>> = LazyLock::new(|| {
+    EnumMap::from_fn(|k: ItemType| {
+        ITEM_DATA
+            .iter()
+            .find(|d| d.item_type == k)
+            .unwrap_or_else(|| panic!("User-defined ItemData not found for: {k:?}"))
+    })
+});
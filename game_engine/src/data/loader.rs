@@ -8,15 +8,18 @@ use enum_map::EnumMap;
 use crate::{
     data::{
         block::{self, BLOCK_DATA, BlockType, TEXTURE_FOLDER},
+        entity::{EntityType, MODEL_FOLDER},
         item::{self, ICON_PATH, ITEM_DATA, ItemType},
     },
-    render::{context::DrawContext, texture::Texture},
+    render::{context::DrawContext, model::Model, texture::Texture},
 };
 
 /// Instantiated item data
 #[derive(Debug, Clone)]
 pub struct ItemData {
     pub texture: ImageSource<'static>,
+    /// The icon's layer in the block texture array, for drawing the item out in the world
+    pub texture_index: u32,
     pub data: item::ItemData,
 }
 
@@ -54,8 +57,11 @@ pub fn init_item_info(draw_context: &DrawContext, egui_renderer: &mut Renderer)
     let map = ITEM_DATA
         .iter()
         .zip(icons)
-        .map(|(d, icon)| ItemData {
+        .enumerate()
+        .map(|(i, (d, icon))| ItemData {
             texture: icon,
+            // Icons go after the block textures
+            texture_index: (BLOCK_DATA.len() + i) as u32,
             data: d.clone(),
         })
         .collect::<Vec<_>>();
@@ -84,9 +90,11 @@ pub static BLOCK_TEXTURES: OnceLock<Texture> = OnceLock::new();
 /// Initialise block info
 pub fn init_block_info(draw_context: &DrawContext) {
     let texture_folder = Path::new(TEXTURE_FOLDER);
+    let icon_folder = Path::new(ICON_PATH);
     let texture_paths = BLOCK_DATA
         .iter()
         .map(|b| texture_folder.join(b.texture_path))
+        .chain(ITEM_DATA.iter().map(|i| icon_folder.join(i.icon_path)))
         .collect::<Vec<_>>();
     let texture_paths = texture_paths
         .iter()
@@ -121,3 +129,19 @@ pub fn init_block_info(draw_context: &DrawContext) {
     BLOCKS.set(map).unwrap();
     BLOCK_TEXTURES.set(textures).unwrap();
 }
+
+/// Load the model for each type of entity
+pub fn load_entity_models(
+    draw_context: &DrawContext,
+    layout: &wgpu::BindGroupLayout,
+) -> EnumMap<EntityType, Model> {
+    let model_folder = Path::new(MODEL_FOLDER);
+
+    EnumMap::from_fn(|k: EntityType| {
+        let path = model_folder.join(k.data().model_path);
+
+        Model::load_model(&path, &draw_context.device, &draw_context.queue, layout)
+            .with_context(|| format!("Failed to load {k:?} model: {path:?}"))
+            .unwrap()
+    })
+}
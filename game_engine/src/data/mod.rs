@@ -1,5 +1,6 @@
 pub mod biome;
 pub mod block;
+pub mod entity;
 pub mod item;
 pub mod loader;
 pub mod world_gen;
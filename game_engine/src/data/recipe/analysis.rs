@@ -0,0 +1,393 @@
+/*
+Static analysis of the recipe graph. Used for balancing & sanity checking recipe data.
+*/
+
+use std::fmt::Write;
+
+use enum_map::{Enum, EnumMap};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::data::{block::BLOCK_DATA, item::ItemType, recipe::Recipe};
+
+/// Total cost of producing one of an item from raw materials
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ItemCost {
+    pub raw_materials: FxHashMap<ItemType, f32>,
+    pub crafting_juice: f32,
+}
+
+impl ItemCost {
+    /// Add on the cost of `amount` of another item
+    fn add_scaled(&mut self, other: &ItemCost, amount: f32) {
+        other.raw_materials.iter().for_each(|(item, count)| {
+            *self.raw_materials.entry(*item).or_default() += count * amount;
+        });
+        self.crafting_juice += other.crafting_juice * amount;
+    }
+}
+
+/// Dependency graph between items, linked by recipes
+pub struct RecipeGraph<'a> {
+    recipes: &'a [Recipe],
+    /// Indices of the recipes which produce each item
+    producers: EnumMap<ItemType, Vec<usize>>,
+}
+
+impl<'a> RecipeGraph<'a> {
+    pub fn new(recipes: &'a [Recipe]) -> Self {
+        let mut producers = EnumMap::<ItemType, Vec<usize>>::default();
+        recipes.iter().enumerate().for_each(|(i, recipe)| {
+            recipe
+                .outputs
+                .iter()
+                .for_each(|(item, _)| producers[*item].push(i));
+        });
+
+        Self { recipes, producers }
+    }
+
+    /// Items which can't be crafted as a guaranteed output
+    #[inline]
+    pub fn is_raw(&self, item: ItemType) -> bool {
+        self.producers[item].is_empty()
+    }
+
+    /// All items which can't be crafted
+    pub fn raw_materials(&self) -> Vec<ItemType> {
+        all_items().filter(|item| self.is_raw(*item)).collect()
+    }
+
+    /// Total raw materials & crafting juice needed to produce one of an item.
+    /// The first recipe which isn't part of a cycle is used, with the full cost attributed to this
+    /// item. Catalysts are costed by their expected consumption. None if the item can only be
+    /// produced through a cycle.
+    pub fn cost(&self, item: ItemType) -> Option<ItemCost> {
+        self.cost_inner(item, &mut vec![])
+    }
+
+    fn cost_inner(&self, item: ItemType, path: &mut Vec<ItemType>) -> Option<ItemCost> {
+        if self.is_raw(item) {
+            return Some(ItemCost {
+                raw_materials: FxHashMap::from_iter([(item, 1.)]),
+                crafting_juice: 0.,
+            });
+        }
+
+        // Went round in a circle
+        if path.contains(&item) {
+            return None;
+        }
+
+        path.push(item);
+        let cost = self.producers[item].iter().find_map(|&i| {
+            let recipe = &self.recipes[i];
+            let (_, output_count) = recipe.outputs.iter().find(|(o, _)| *o == item)?;
+            let per_output = 1. / *output_count as f32;
+
+            let mut cost = ItemCost {
+                crafting_juice: recipe.crafting_juice_cost * per_output,
+                ..Default::default()
+            };
+            let inputs = recipe
+                .inputs
+                .iter()
+                .map(|(input, count)| (*input, *count as f32))
+                .chain(
+                    recipe
+                        .catalysts
+                        .iter()
+                        .map(|c| (c.item, c.count as f32 * c.consume_chance)),
+                );
+            for (input, count) in inputs {
+                let input_cost = self.cost_inner(input, path)?;
+                cost.add_scaled(&input_cost, count * per_output);
+            }
+
+            Some(cost)
+        });
+        path.pop();
+
+        cost
+    }
+
+    /// Find loops of items which can be crafted into each other. Each cycle is listed once per
+    /// entry point, starting and ending at the same item.
+    pub fn find_cycles(&self) -> Vec<Vec<ItemType>> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum State {
+            Unvisited,
+            InProgress,
+            Done,
+        }
+
+        fn visit(
+            graph: &RecipeGraph,
+            item: ItemType,
+            states: &mut EnumMap<ItemType, State>,
+            path: &mut Vec<ItemType>,
+            cycles: &mut Vec<Vec<ItemType>>,
+        ) {
+            states[item] = State::InProgress;
+            path.push(item);
+
+            graph.outputs_of(item).for_each(|next| match states[next] {
+                State::Unvisited => visit(graph, next, states, path, cycles),
+                State::InProgress => {
+                    // Back edge, take the loop off the current path
+                    let start = path.iter().position(|i| *i == next).unwrap();
+                    let mut cycle = path[start..].to_vec();
+                    cycle.push(next);
+                    cycles.push(cycle);
+                }
+                State::Done => {}
+            });
+
+            path.pop();
+            states[item] = State::Done;
+        }
+
+        let mut states = EnumMap::from_fn(|_| State::Unvisited);
+        let mut cycles = vec![];
+        all_items().for_each(|item| {
+            if states[item] == State::Unvisited {
+                visit(self, item, &mut states, &mut vec![], &mut cycles);
+            }
+        });
+
+        cycles
+    }
+
+    /// Items which can be directly crafted using the given item
+    fn outputs_of(&self, item: ItemType) -> impl Iterator<Item = ItemType> {
+        self.recipes
+            .iter()
+            .filter(move |r| r.requires().any(|i| i == item))
+            .flat_map(|r| r.produces())
+            .collect::<FxHashSet<_>>()
+            .into_iter()
+    }
+
+    /// Items which can never be obtained, starting from the given sources
+    pub fn unreachable_items(&self, sources: &[ItemType]) -> Vec<ItemType> {
+        let mut reachable = EnumMap::<ItemType, bool>::default();
+        sources.iter().for_each(|item| reachable[*item] = true);
+
+        // Keep crafting until nothing new shows up
+        let mut changed = true;
+        while changed {
+            changed = false;
+            self.recipes.iter().for_each(|recipe| {
+                if recipe.requires().all(|item| reachable[item]) {
+                    recipe.produces().for_each(|item| {
+                        changed |= !reachable[item];
+                        reachable[item] = true;
+                    });
+                }
+            });
+        }
+
+        reachable
+            .into_iter()
+            .filter(|(_, reachable)| !reachable)
+            .map(|(item, _)| item)
+            .collect()
+    }
+
+    /// Export the graph in Graphviz DOT format. Recipes are drawn as boxes between their inputs &
+    /// outputs.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph recipes {\n");
+
+        all_items().for_each(|item| {
+            writeln!(dot, "    {item:?} [label=\"{}\"];", item.data().name).unwrap();
+        });
+
+        self.recipes.iter().enumerate().for_each(|(i, recipe)| {
+            writeln!(
+                dot,
+                "    recipe_{i} [shape=box, label=\"{} juice\"];",
+                recipe.crafting_juice_cost
+            )
+            .unwrap();
+
+            // Sort inputs so the output is stable
+            let mut inputs = recipe.inputs.iter().collect::<Vec<_>>();
+            inputs.sort_by_key(|(item, _)| item.into_usize());
+            inputs.into_iter().for_each(|(item, count)| {
+                writeln!(dot, "    {item:?} -> recipe_{i} [label=\"{count}\"];").unwrap();
+            });
+            recipe.catalysts.iter().for_each(|c| {
+                writeln!(
+                    dot,
+                    "    {:?} -> recipe_{i} [label=\"{} ({}%)\", style=dashed];",
+                    c.item,
+                    c.count,
+                    c.consume_chance * 100.
+                )
+                .unwrap();
+            });
+
+            recipe.outputs.iter().for_each(|(item, count)| {
+                writeln!(dot, "    recipe_{i} -> {item:?} [label=\"{count}\"];").unwrap();
+            });
+            recipe.byproducts.iter().for_each(|b| {
+                writeln!(
+                    dot,
+                    "    recipe_{i} -> {:?} [label=\"{} ({}%)\", style=dashed];",
+                    b.item,
+                    b.count,
+                    b.chance * 100.
+                )
+                .unwrap();
+            });
+        });
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Items which can be collected by breaking blocks
+pub fn block_drops() -> Vec<ItemType> {
+    BLOCK_DATA
+        .iter()
+        .filter(|b| b.hardness.is_some())
+        .filter_map(|b| b.item_on_break)
+        .collect()
+}
+
+fn all_items() -> impl Iterator<Item = ItemType> {
+    (0..ItemType::LENGTH).map(ItemType::from_usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_hash::FxHashMap;
+
+    use crate::data::{
+        item::ItemType,
+        recipe::{
+            Byproduct, Catalyst, Recipe, all_recipes,
+            analysis::{RecipeGraph, block_drops},
+        },
+    };
+
+    #[test]
+    fn test_tool_progression() {
+        use ItemType::*;
+
+        // Bars come out of furnaces, which burn fuel rather than juice
+        let recipes = all_recipes();
+        let graph = RecipeGraph::new(&recipes);
+
+        // Copper -> Bronze -> Iron -> Steel -> Magic Metal
+        let tiers = [
+            (CopperPickaxe, vec![(Copper, 5.)], 10.),
+            (BronzePickaxe, vec![(Copper, 2.5), (Tin, 2.5)], 10.),
+            (IronPickaxe, vec![(Iron, 5.)], 10.),
+            (SteelPickaxe, vec![(Iron, 5.), (Coal, 5.)], 10.),
+            (
+                MagicMetalPickaxe,
+                vec![(MagicMetal, 5.), (RadioactiveStone, 0.1)],
+                10.,
+            ),
+        ];
+
+        tiers.iter().for_each(|(tool, raw, juice)| {
+            let cost = graph.cost(*tool).unwrap();
+            assert_eq!(
+                cost.raw_materials,
+                FxHashMap::from_iter(raw.iter().cloned()),
+                "{tool:?}"
+            );
+            assert_eq!(cost.crafting_juice, *juice, "{tool:?}");
+        });
+
+        // Each tier must be stronger than the last
+        tiers.array_windows().for_each(|[(prev, ..), (next, ..)]| {
+            assert!(
+                prev.data().breaking_strength < next.data().breaking_strength,
+                "{prev:?} should be weaker than {next:?}"
+            );
+        });
+    }
+
+    #[test]
+    fn test_recipes_sane() {
+        // Machines make some items, so they're all needed to reach everything
+        let recipes = all_recipes();
+        let graph = RecipeGraph::new(&recipes);
+
+        assert_eq!(graph.find_cycles(), Vec::<Vec<ItemType>>::new());
+        assert_eq!(
+            graph.unreachable_items(&block_drops()),
+            Vec::<ItemType>::new()
+        );
+    }
+
+    #[test]
+    fn test_cycles() {
+        let recipes = [
+            Recipe {
+                inputs: FxHashMap::from_iter([(ItemType::Stone, 1)]),
+                outputs: vec![(ItemType::DarkStone, 1)],
+                byproducts: vec![],
+                catalysts: vec![],
+                crafting_juice_cost: 1.,
+                unlock: vec![],
+                automation_crafts: 0,
+            },
+            Recipe {
+                inputs: FxHashMap::from_iter([(ItemType::DarkStone, 1)]),
+                outputs: vec![(ItemType::Stone, 1)],
+                byproducts: vec![],
+                catalysts: vec![],
+                crafting_juice_cost: 1.,
+                unlock: vec![],
+                automation_crafts: 0,
+            },
+        ];
+        let graph = RecipeGraph::new(&recipes);
+
+        assert_eq!(
+            graph.find_cycles(),
+            vec![vec![ItemType::Stone, ItemType::DarkStone, ItemType::Stone]]
+        );
+        assert!(graph.cost(ItemType::Stone).is_none());
+
+        // Nothing to start the loop off
+        let unreachable = graph.unreachable_items(&[]);
+        assert!(unreachable.contains(&ItemType::Stone));
+        assert!(unreachable.contains(&ItemType::DarkStone));
+    }
+
+    #[test]
+    fn test_dot_export() {
+        let recipes = [Recipe {
+            inputs: FxHashMap::from_iter([(ItemType::Copper, 1), (ItemType::Tin, 1)]),
+            outputs: vec![(ItemType::Bronze, 2)],
+            byproducts: vec![Byproduct {
+                item: ItemType::Slag,
+                count: 1,
+                chance: 0.25,
+            }],
+            catalysts: vec![Catalyst {
+                item: ItemType::Coal,
+                count: 1,
+                consume_chance: 0.5,
+            }],
+            crafting_juice_cost: 3.,
+            unlock: vec![],
+            automation_crafts: 0,
+        }];
+        let dot = RecipeGraph::new(&recipes).to_dot();
+
+        assert!(dot.starts_with("digraph recipes {"));
+        assert!(dot.contains("recipe_0 [shape=box, label=\"3 juice\"];"));
+        assert!(dot.contains("Copper -> recipe_0 [label=\"1\"];"));
+        assert!(dot.contains("Tin -> recipe_0 [label=\"1\"];"));
+        assert!(dot.contains("Coal -> recipe_0 [label=\"1 (50%)\", style=dashed];"));
+        assert!(dot.contains("recipe_0 -> Bronze [label=\"2\"];"));
+        assert!(dot.contains("recipe_0 -> Slag [label=\"1 (25%)\", style=dashed];"));
+    }
+}
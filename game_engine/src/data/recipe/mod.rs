@@ -0,0 +1,488 @@
+pub mod analysis;
+pub mod processing;
+pub mod smelting;
+
+use std::{fmt::Display, sync::LazyLock};
+
+use enum_map::EnumMap;
+use rand::Rng;
+use rustc_hash::FxHashMap;
+
+use crate::data::{
+    item::ItemType,
+    recipe::{
+        processing::{CRUSHING_RECIPES, WASHING_RECIPES},
+        smelting::SMELTING_AS_RECIPES,
+    },
+};
+
+/// Requirement for a recipe to become available
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnlockCondition {
+    /// The item has been held at some point
+    Discovered(ItemType),
+    /// The item has been crafted by hand at least this many times
+    Crafted(ItemType, usize),
+}
+
+impl Display for UnlockCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnlockCondition::Discovered(item) => write!(f, "Discover {}", item.data().name),
+            UnlockCondition::Crafted(item, count) => {
+                write!(f, "Craft {count} {} by hand", item.data().name)
+            }
+        }
+    }
+}
+
+/// Which kind of machine makes a recipe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecipeCategory {
+    /// Crafters & crafting by hand
+    #[default]
+    Crafting,
+    /// Furnaces, ingredients into bars using fuel
+    Smelting,
+    /// Crushers, ores into dust
+    Crushing,
+    /// Washers, dust into bars
+    Washing,
+}
+
+impl RecipeCategory {
+    pub const ALL: [RecipeCategory; 4] = [
+        RecipeCategory::Crafting,
+        RecipeCategory::Smelting,
+        RecipeCategory::Crushing,
+        RecipeCategory::Washing,
+    ];
+
+    /// Every recipe made by this kind of machine
+    pub fn recipes(&self) -> &'static [Recipe] {
+        match self {
+            RecipeCategory::Crafting => &RECIPES,
+            RecipeCategory::Smelting => &SMELTING_AS_RECIPES,
+            RecipeCategory::Crushing => &CRUSHING_RECIPES,
+            RecipeCategory::Washing => &WASHING_RECIPES,
+        }
+    }
+
+    /// Name of the machine which makes these recipes
+    pub fn machine_name(&self) -> &'static str {
+        match self {
+            RecipeCategory::Crafting => "Crafter",
+            RecipeCategory::Smelting => "Furnace",
+            RecipeCategory::Crushing => "Crusher",
+            RecipeCategory::Washing => "Washer",
+        }
+    }
+}
+
+/// Every recipe of every category, crafting first, then smelting
+pub fn all_recipes() -> Vec<Recipe> {
+    RecipeCategory::ALL
+        .iter()
+        .flat_map(|category| category.recipes().iter().cloned())
+        .collect()
+}
+
+/// Output which is only produced some of the time
+#[derive(Debug, Clone, PartialEq)]
+pub struct Byproduct {
+    pub item: ItemType,
+    pub count: usize,
+    /// 0-1 chance of being produced per craft
+    pub chance: f32,
+}
+
+/// Item which must be present to craft, but isn't always used up
+#[derive(Debug, Clone, PartialEq)]
+pub struct Catalyst {
+    pub item: ItemType,
+    pub count: usize,
+    /// 0-1 chance of being used up per craft
+    pub consume_chance: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Recipe {
+    pub inputs: FxHashMap<ItemType, usize>,
+    /// Guaranteed outputs, the first is the main product
+    pub outputs: Vec<(ItemType, usize)>,
+    pub byproducts: Vec<Byproduct>,
+    pub catalysts: Vec<Catalyst>,
+    pub crafting_juice_cost: f32,
+    /// Conditions for the recipe to show up for crafting by hand
+    pub unlock: Vec<UnlockCondition>,
+    /// Number of times the output must be crafted by hand before it can be automated
+    pub automation_crafts: usize,
+}
+
+/// Catalysts consumed & byproducts produced by one craft
+pub type CraftRoll = (Vec<(ItemType, usize)>, Vec<(ItemType, usize)>);
+
+impl Recipe {
+    /// The main product of the recipe
+    #[inline]
+    pub fn output(&self) -> (ItemType, usize) {
+        self.outputs[0]
+    }
+
+    /// Every item the recipe can produce, including byproducts
+    pub fn produces(&self) -> impl Iterator<Item = ItemType> {
+        self.outputs
+            .iter()
+            .map(|(item, _)| *item)
+            .chain(self.byproducts.iter().map(|b| b.item))
+    }
+
+    /// Most one craft can produce, every byproduct included
+    pub fn most_outputs(&self) -> Vec<(ItemType, usize)> {
+        self.outputs
+            .iter()
+            .cloned()
+            .chain(self.byproducts.iter().map(|b| (b.item, b.count)))
+            .collect()
+    }
+
+    /// Every item which must be present to craft, including catalysts
+    pub fn requires(&self) -> impl Iterator<Item = ItemType> {
+        self.inputs
+            .keys()
+            .cloned()
+            .chain(self.catalysts.iter().map(|c| c.item))
+    }
+
+    /// Whether there's enough items to craft the recipe once
+    pub fn can_craft(&self, items: &EnumMap<ItemType, usize>) -> bool {
+        let have_inputs = self
+            .inputs
+            .iter()
+            .all(|(item, count)| items[*item] >= *count);
+
+        // Catalysts need to be present on top of any inputs of the same type
+        let have_catalysts = self
+            .catalysts
+            .iter()
+            .all(|c| items[c.item] >= c.count + self.inputs.get(&c.item).copied().unwrap_or(0));
+
+        have_inputs && have_catalysts
+    }
+
+    /// Most times the inputs allow crafting in a row. Catalysts are checked craft by craft, since
+    /// whether they're used up is random.
+    pub fn max_crafts(&self, items: &EnumMap<ItemType, usize>) -> usize {
+        self.inputs
+            .iter()
+            .map(|(item, count)| items[*item] / count)
+            .min()
+            .unwrap_or(usize::MAX)
+    }
+
+    /// Roll which catalysts get used up and which byproducts get produced for one craft
+    pub fn roll(&self, rng: &mut impl Rng) -> CraftRoll {
+        let consumed = self
+            .catalysts
+            .iter()
+            .filter(|c| rng.random::<f32>() < c.consume_chance)
+            .map(|c| (c.item, c.count))
+            .collect();
+
+        let byproducts = self
+            .byproducts
+            .iter()
+            .filter(|b| rng.random::<f32>() < b.chance)
+            .map(|b| (b.item, b.count))
+            .collect();
+
+        (consumed, byproducts)
+    }
+}
+
+pub static RECIPES: LazyLock<Vec<Recipe>> = LazyLock::new(|| {
+    vec![
+        Recipe {
+            inputs: { FxHashMap::from_iter([(ItemType::Stone, 4)]) },
+            outputs: vec![(ItemType::DarkStone, 1)],
+            byproducts: vec![],
+            catalysts: vec![],
+            crafting_juice_cost: 10.,
+            unlock: vec![],
+            automation_crafts: 5,
+        },
+        Recipe {
+            inputs: { FxHashMap::from_iter([(ItemType::Dirt, 1), (ItemType::Stone, 2)]) },
+            outputs: vec![(ItemType::MossyStone, 2)],
+            byproducts: vec![],
+            catalysts: vec![],
+            crafting_juice_cost: 10.,
+            unlock: vec![],
+            automation_crafts: 5,
+        },
+        Recipe {
+            inputs: { FxHashMap::from_iter([(ItemType::Copper, 5)]) },
+            outputs: vec![(ItemType::CopperPickaxe, 1)],
+            byproducts: vec![],
+            catalysts: vec![],
+            crafting_juice_cost: 10.,
+            unlock: vec![UnlockCondition::Discovered(ItemType::Copper)],
+            automation_crafts: 1,
+        },
+        Recipe {
+            inputs: { FxHashMap::from_iter([(ItemType::Bronze, 5)]) },
+            outputs: vec![(ItemType::BronzePickaxe, 1)],
+            byproducts: vec![],
+            catalysts: vec![],
+            crafting_juice_cost: 10.,
+            unlock: vec![UnlockCondition::Crafted(ItemType::CopperPickaxe, 1)],
+            automation_crafts: 1,
+        },
+        Recipe {
+            inputs: { FxHashMap::from_iter([(ItemType::Iron, 5)]) },
+            outputs: vec![(ItemType::IronPickaxe, 1)],
+            byproducts: vec![],
+            catalysts: vec![],
+            crafting_juice_cost: 10.,
+            unlock: vec![UnlockCondition::Crafted(ItemType::BronzePickaxe, 1)],
+            automation_crafts: 1,
+        },
+        Recipe {
+            inputs: { FxHashMap::from_iter([(ItemType::Steel, 5)]) },
+            outputs: vec![(ItemType::SteelPickaxe, 1)],
+            byproducts: vec![],
+            catalysts: vec![],
+            crafting_juice_cost: 10.,
+            unlock: vec![UnlockCondition::Crafted(ItemType::IronPickaxe, 1)],
+            automation_crafts: 1,
+        },
+        Recipe {
+            inputs: { FxHashMap::from_iter([(ItemType::MagicMetal, 5)]) },
+            outputs: vec![(ItemType::MagicMetalPickaxe, 1)],
+            byproducts: vec![],
+            catalysts: vec![Catalyst {
+                item: ItemType::RadioactiveStone,
+                count: 1,
+                consume_chance: 0.1,
+            }],
+            crafting_juice_cost: 10.,
+            unlock: vec![UnlockCondition::Crafted(ItemType::SteelPickaxe, 1)],
+            automation_crafts: 1,
+        },
+        Recipe {
+            inputs: { FxHashMap::from_iter([(ItemType::Copper, 2)]) },
+            outputs: vec![(ItemType::CopperShovel, 1)],
+            byproducts: vec![],
+            catalysts: vec![],
+            crafting_juice_cost: 10.,
+            unlock: vec![UnlockCondition::Discovered(ItemType::Copper)],
+            automation_crafts: 1,
+        },
+        Recipe {
+            inputs: { FxHashMap::from_iter([(ItemType::Copper, 3)]) },
+            outputs: vec![(ItemType::CopperAxe, 1)],
+            byproducts: vec![],
+            catalysts: vec![],
+            crafting_juice_cost: 10.,
+            unlock: vec![UnlockCondition::Discovered(ItemType::Copper)],
+            automation_crafts: 1,
+        },
+        Recipe {
+            inputs: { FxHashMap::from_iter([(ItemType::MagicMetal, 5), (ItemType::Steel, 2)]) },
+            outputs: vec![(ItemType::MagicMetalDrill, 1)],
+            byproducts: vec![],
+            catalysts: vec![],
+            crafting_juice_cost: 50.,
+            unlock: vec![UnlockCondition::Crafted(ItemType::MagicMetalPickaxe, 1)],
+            automation_crafts: 1,
+        },
+        Recipe {
+            inputs: { FxHashMap::from_iter([(ItemType::Iron, 3), (ItemType::Chest, 1)]) },
+            outputs: vec![(ItemType::Hopper, 1)],
+            byproducts: vec![],
+            catalysts: vec![],
+            crafting_juice_cost: 20.,
+            unlock: vec![UnlockCondition::Discovered(ItemType::Iron)],
+            automation_crafts: 1,
+        },
+        Recipe {
+            inputs: { FxHashMap::from_iter([(ItemType::Iron, 1), (ItemType::Stone, 2)]) },
+            outputs: vec![(ItemType::Conveyor, 4)],
+            byproducts: vec![],
+            catalysts: vec![],
+            crafting_juice_cost: 10.,
+            unlock: vec![UnlockCondition::Discovered(ItemType::Iron)],
+            automation_crafts: 1,
+        },
+        Recipe {
+            inputs: { FxHashMap::from_iter([(ItemType::Stone, 8), (ItemType::Copper, 2)]) },
+            outputs: vec![(ItemType::Generator, 1)],
+            byproducts: vec![],
+            catalysts: vec![],
+            crafting_juice_cost: 10.,
+            unlock: vec![UnlockCondition::Discovered(ItemType::Coal)],
+            automation_crafts: 1,
+        },
+        Recipe {
+            inputs: { FxHashMap::from_iter([(ItemType::Copper, 1)]) },
+            outputs: vec![(ItemType::Conduit, 4)],
+            byproducts: vec![],
+            catalysts: vec![],
+            crafting_juice_cost: 5.,
+            unlock: vec![UnlockCondition::Discovered(ItemType::Copper)],
+            automation_crafts: 1,
+        },
+        Recipe {
+            inputs: {
+                FxHashMap::from_iter([
+                    (ItemType::RadioactiveStone, 8),
+                    (ItemType::Steel, 4),
+                    (ItemType::Generator, 1),
+                ])
+            },
+            outputs: vec![(ItemType::Reactor, 1)],
+            byproducts: vec![],
+            catalysts: vec![],
+            crafting_juice_cost: 100.,
+            unlock: vec![UnlockCondition::Discovered(ItemType::RadioactiveStone)],
+            automation_crafts: 1,
+        },
+        Recipe {
+            inputs: { FxHashMap::from_iter([(ItemType::Stone, 8)]) },
+            outputs: vec![(ItemType::Furnace, 1)],
+            byproducts: vec![],
+            catalysts: vec![],
+            crafting_juice_cost: 10.,
+            unlock: vec![UnlockCondition::Discovered(ItemType::Coal)],
+            automation_crafts: 1,
+        },
+        Recipe {
+            inputs: {
+                FxHashMap::from_iter([
+                    (ItemType::Steel, 4),
+                    (ItemType::MagicMetal, 2),
+                    (ItemType::VoidStone, 4),
+                ])
+            },
+            outputs: vec![(ItemType::ChunkLoader, 1)],
+            byproducts: vec![],
+            catalysts: vec![],
+            crafting_juice_cost: 50.,
+            unlock: vec![UnlockCondition::Discovered(ItemType::MagicMetal)],
+            automation_crafts: 1,
+        },
+        Recipe {
+            inputs: {
+                FxHashMap::from_iter([
+                    (ItemType::Chest, 1),
+                    (ItemType::Iron, 4),
+                    (ItemType::Copper, 4),
+                ])
+            },
+            outputs: vec![(ItemType::StorageTerminal, 1)],
+            byproducts: vec![],
+            catalysts: vec![],
+            crafting_juice_cost: 20.,
+            unlock: vec![UnlockCondition::Discovered(ItemType::Chest)],
+            automation_crafts: 1,
+        },
+        Recipe {
+            inputs: {
+                FxHashMap::from_iter([
+                    (ItemType::Crafter, 1),
+                    (ItemType::Iron, 6),
+                    (ItemType::Stone, 8),
+                ])
+            },
+            outputs: vec![(ItemType::Crusher, 1)],
+            byproducts: vec![],
+            catalysts: vec![],
+            crafting_juice_cost: 30.,
+            unlock: vec![UnlockCondition::Discovered(ItemType::Iron)],
+            automation_crafts: 1,
+        },
+        Recipe {
+            inputs: {
+                FxHashMap::from_iter([
+                    (ItemType::Crafter, 1),
+                    (ItemType::Bronze, 6),
+                    (ItemType::Steel, 2),
+                ])
+            },
+            outputs: vec![(ItemType::Washer, 1)],
+            byproducts: vec![],
+            catalysts: vec![],
+            crafting_juice_cost: 30.,
+            unlock: vec![UnlockCondition::Discovered(ItemType::CopperDust)],
+            automation_crafts: 1,
+        },
+    ]
+});
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::SmallRng};
+    use rustc_hash::FxHashMap;
+
+    use crate::{
+        data::{
+            item::ItemType,
+            recipe::{Byproduct, Catalyst, Recipe},
+        },
+        entity::components::Inventory,
+    };
+
+    #[test]
+    fn test_catalysts() {
+        let recipe = |consume_chance, chance| Recipe {
+            inputs: FxHashMap::from_iter([(ItemType::Stone, 1)]),
+            outputs: vec![(ItemType::DarkStone, 1)],
+            byproducts: vec![Byproduct {
+                item: ItemType::Slag,
+                count: 1,
+                chance,
+            }],
+            catalysts: vec![Catalyst {
+                item: ItemType::Coal,
+                count: 1,
+                consume_chance,
+            }],
+            crafting_juice_cost: 1.,
+            unlock: vec![],
+            automation_crafts: 0,
+        };
+        let kept = recipe(0., 1.);
+
+        // Catalysts have to be there to craft
+        let mut inventory = Inventory::default();
+        inventory.add_item(ItemType::Stone, 2);
+        assert!(!kept.can_craft(&inventory.items));
+        inventory.add_item(ItemType::Coal, 1);
+        assert!(kept.can_craft(&inventory.items));
+
+        // But aren't used up unless the roll says so
+        let mut rng = SmallRng::seed_from_u64(1);
+        inventory.craft_recipe(&kept, &mut rng);
+        assert_eq!(inventory.items[ItemType::Coal], 1);
+        assert_eq!(inventory.items[ItemType::Stone], 1);
+        assert_eq!(inventory.items[ItemType::Slag], 1);
+        inventory.craft_recipe(&recipe(1., 0.), &mut rng);
+        assert_eq!(inventory.items[ItemType::Coal], 0);
+        assert_eq!(inventory.items[ItemType::Slag], 1);
+
+        // The same seed always rolls the same way
+        let rolls = |seed| {
+            let mut rng = SmallRng::seed_from_u64(seed);
+            (0..20)
+                .map(|_| recipe(0.5, 0.5).roll(&mut rng))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(rolls(7), rolls(7));
+        assert!(rolls(7).iter().any(|(consumed, _)| consumed.is_empty()));
+        assert!(
+            rolls(7)
+                .iter()
+                .any(|(_, byproducts)| !byproducts.is_empty())
+        );
+    }
+}
@@ -0,0 +1,132 @@
+/*
+Recipes for the ore processing machines. Ores are crushed into dust, then the dust is washed into
+more bars than smelting the ore directly would give.
+Machines run these the same way crafters run crafting recipes, using crafting juice.
+*/
+
+use std::sync::LazyLock;
+
+use rustc_hash::FxHashMap;
+
+use crate::data::{
+    item::ItemType,
+    recipe::{Byproduct, Recipe, UnlockCondition},
+};
+
+pub static CRUSHING_RECIPES: LazyLock<Vec<Recipe>> = LazyLock::new(|| {
+    vec![
+        Recipe {
+            inputs: { FxHashMap::from_iter([(ItemType::Copper, 1)]) },
+            outputs: vec![(ItemType::CopperDust, 2)],
+            byproducts: vec![Byproduct {
+                item: ItemType::TinDust,
+                count: 1,
+                chance: 0.1,
+            }],
+            catalysts: vec![],
+            crafting_juice_cost: 5.,
+            unlock: vec![UnlockCondition::Discovered(ItemType::Copper)],
+            automation_crafts: 0,
+        },
+        Recipe {
+            inputs: { FxHashMap::from_iter([(ItemType::Tin, 1)]) },
+            outputs: vec![(ItemType::TinDust, 2)],
+            byproducts: vec![Byproduct {
+                item: ItemType::Stone,
+                count: 1,
+                chance: 0.25,
+            }],
+            catalysts: vec![],
+            crafting_juice_cost: 5.,
+            unlock: vec![UnlockCondition::Discovered(ItemType::Tin)],
+            automation_crafts: 0,
+        },
+        Recipe {
+            inputs: { FxHashMap::from_iter([(ItemType::Iron, 1)]) },
+            outputs: vec![(ItemType::IronDust, 2)],
+            byproducts: vec![Byproduct {
+                item: ItemType::Coal,
+                count: 1,
+                chance: 0.1,
+            }],
+            catalysts: vec![],
+            crafting_juice_cost: 8.,
+            unlock: vec![UnlockCondition::Discovered(ItemType::Iron)],
+            automation_crafts: 0,
+        },
+    ]
+});
+
+pub static WASHING_RECIPES: LazyLock<Vec<Recipe>> = LazyLock::new(|| {
+    vec![
+        Recipe {
+            inputs: { FxHashMap::from_iter([(ItemType::CopperDust, 2), (ItemType::TinDust, 2)]) },
+            outputs: vec![(ItemType::Bronze, 5)],
+            byproducts: vec![Byproduct {
+                item: ItemType::Slag,
+                count: 1,
+                chance: 0.25,
+            }],
+            catalysts: vec![],
+            crafting_juice_cost: 10.,
+            unlock: vec![UnlockCondition::Discovered(ItemType::TinDust)],
+            automation_crafts: 0,
+        },
+        Recipe {
+            inputs: { FxHashMap::from_iter([(ItemType::IronDust, 2), (ItemType::Coal, 1)]) },
+            outputs: vec![(ItemType::Steel, 3)],
+            byproducts: vec![Byproduct {
+                item: ItemType::Slag,
+                count: 1,
+                chance: 0.5,
+            }],
+            catalysts: vec![],
+            crafting_juice_cost: 15.,
+            unlock: vec![UnlockCondition::Discovered(ItemType::IronDust)],
+            automation_crafts: 0,
+        },
+    ]
+});
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        data::{item::ItemType, recipe::RecipeCategory},
+        entity::components::{Crafter, CraftingJob, JobTarget},
+        tick::Tickable,
+    };
+
+    #[test]
+    fn test_ore_processing() {
+        // Two of each recipe, in order
+        let machine = |category: RecipeCategory| {
+            let mut machine = Crafter {
+                category,
+                juice_per_second: 10.,
+                power: 1.,
+                ..Default::default()
+            };
+            category.recipes().iter().for_each(|recipe| {
+                machine.queue.push(CraftingJob {
+                    recipe: recipe.clone(),
+                    target: JobTarget::Repeat(2),
+                });
+            });
+            machine
+        };
+
+        let mut crusher = machine(RecipeCategory::Crushing);
+        crusher.input.add_item(ItemType::Copper, 2);
+        crusher.input.add_item(ItemType::Tin, 2);
+        crusher.tick(10.);
+        assert_eq!(crusher.output.items[ItemType::CopperDust], 4);
+        assert!(crusher.output.items[ItemType::TinDust] >= 4);
+
+        // Washing the dust from one copper & one tin ore gives more than smelting them
+        let mut washer = machine(RecipeCategory::Washing);
+        washer.input.add_item(ItemType::CopperDust, 4);
+        washer.input.add_item(ItemType::TinDust, 4);
+        washer.tick(10.);
+        assert_eq!(washer.output.items[ItemType::Bronze], 10);
+    }
+}
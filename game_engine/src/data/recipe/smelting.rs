@@ -0,0 +1,70 @@
+/*
+Recipes which are cooked in a furnace using heat from burning fuel, rather than crafting juice.
+The furnace picks the recipe itself based on what's been put in.
+*/
+
+use std::sync::LazyLock;
+
+use enum_map::EnumMap;
+use rustc_hash::FxHashMap;
+
+use crate::data::{item::ItemType, recipe::Recipe};
+
+#[derive(Debug, Clone)]
+pub struct SmeltingRecipe {
+    pub inputs: FxHashMap<ItemType, usize>,
+    pub output: (ItemType, usize),
+    /// Seconds of burning fuel needed to cook once
+    pub cook_time: f32,
+}
+
+impl SmeltingRecipe {
+    /// Whether there's enough items to cook the recipe once
+    pub fn can_smelt(&self, items: &EnumMap<ItemType, usize>) -> bool {
+        self.inputs
+            .iter()
+            .all(|(item, count)| items[*item] >= *count)
+    }
+
+    /// The same recipe in the form other machines use, so it can be analysed alongside them.
+    /// Furnaces burn fuel rather than crafting juice, so it costs no juice.
+    pub fn to_recipe(&self) -> Recipe {
+        Recipe {
+            inputs: self.inputs.clone(),
+            outputs: vec![self.output],
+            byproducts: vec![],
+            catalysts: vec![],
+            crafting_juice_cost: 0.,
+            unlock: vec![],
+            automation_crafts: 0,
+        }
+    }
+}
+
+pub static SMELTING_RECIPES: LazyLock<Vec<SmeltingRecipe>> = LazyLock::new(|| {
+    vec![
+        SmeltingRecipe {
+            inputs: { FxHashMap::from_iter([(ItemType::Copper, 1), (ItemType::Tin, 1)]) },
+            output: (ItemType::Bronze, 2),
+            cook_time: 4.,
+        },
+        SmeltingRecipe {
+            inputs: { FxHashMap::from_iter([(ItemType::Iron, 1), (ItemType::Coal, 1)]) },
+            output: (ItemType::Steel, 1),
+            cook_time: 8.,
+        },
+        SmeltingRecipe {
+            inputs: { FxHashMap::from_iter([(ItemType::Stone, 2)]) },
+            output: (ItemType::DarkStone, 1),
+            cook_time: 3.,
+        },
+    ]
+});
+
+/// `SMELTING_RECIPES` as regular recipes, see `SmeltingRecipe::to_recipe`
+pub static SMELTING_AS_RECIPES: LazyLock<Vec<Recipe>> = LazyLock::new(|| {
+    SMELTING_RECIPES
+        .iter()
+        .map(SmeltingRecipe::to_recipe)
+        .collect()
+});
@@ -7,26 +7,57 @@ use rustc_hash::FxHashMap;
 
 use crate::{
     InteractionMode,
-    entity::components::{Container, Crafter, Hotbar, Reach, UIType, UprightOrientation, Vision},
+    data::entity::EntityType,
+    entity::components::{
+        Behaviour, Brain, ChunkLoader, Conveyor, Crafter, Furnace, Generator, Health, Hopper,
+        Hotbar, Inventory, ItemEntity, ItemFilter, ItemViews, Mining, Movement, Orientation,
+        Position, PowerNode, Progression, Reach, StorageNode, StorageTerminal, Ticker, UIType,
+        UprightOrientation, Vision,
+    },
     math::bbox::AABB,
     state::world::{BlockPos, WorldPos},
 };
 
 // Block Types
-pub type CrafterBlock = (BlockPos, UIType, Container, Crafter);
-pub type Chest = (BlockPos, UIType, Container);
+/// Crafters, crushers & washers
+pub type CrafterBlock = (BlockPos, UIType, Crafter, PowerNode, Ticker);
+pub type Chest = (BlockPos, UIType, Inventory, ItemFilter, StorageNode);
+pub type HopperBlock = (BlockPos, UIType, Hopper);
+pub type ConveyorBlock = (BlockPos, UIType, Conveyor);
+pub type GeneratorBlock = (BlockPos, UIType, Generator, PowerNode);
+pub type ConduitBlock = (BlockPos, UIType, PowerNode);
+pub type FurnaceBlock = (BlockPos, UIType, Furnace, Ticker);
+pub type ChunkLoaderBlock = (BlockPos, UIType, ChunkLoader);
+pub type StorageTerminalBlock = (BlockPos, UIType, StorageTerminal, StorageNode);
+
+// Entity Types
+pub type Mob = (
+    Position,
+    Orientation,
+    EntityType,
+    // Model coordinates AABB
+    AABB<f32>,
+    Health,
+    Movement,
+    Vision,
+    Behaviour,
+    Brain,
+);
 
 pub type Player = (
     WorldPos,
     UprightOrientation,
-    Container,
+    Inventory,
     Hotbar,
     Vision,
     Reach,
     // Model coordinates AABB
     AABB<f32>,
     InteractionMode,
+    Progression,
+    Mining,
+    ItemViews,
 );
-pub type Monster = (WorldPos, UprightOrientation);
+pub type Item = (Position, ItemEntity);
 
 pub type BlockStates = FxHashMap<BlockPos, Entity>;
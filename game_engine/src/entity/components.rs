@@ -1,21 +1,24 @@
 use cgmath::{Point3, Quaternion, Rad, Vector3, Zero};
-use enum_map::EnumMap;
+use enum_map::{Enum, EnumMap};
+use hecs::Entity;
+use rand::{Rng, SeedableRng, rngs::SmallRng};
+use rustc_hash::FxHashMap;
 
 use crate::{
+    container::Side,
     data::{
-        item::ItemType,
-        recipe::{RECIPES, Recipe},
+        item::{ItemCategory, ItemType},
+        recipe::{
+            RECIPES, Recipe, RecipeCategory, UnlockCondition,
+            smelting::{SMELTING_RECIPES, SmeltingRecipe},
+        },
     },
-    entity::EntityId,
+    item::{ItemInstance, ItemStack},
     math::angles_to_vec3,
-    state::world::WorldPos,
+    power::NetworkStatus,
+    state::world::{BlockPos, ChunkPos, ForceLoadError, WorldPos},
 };
 
-#[derive(Debug)]
-pub enum EntityType {
-    Sibeal,
-}
-
 #[derive(Debug, Clone)]
 pub struct Position(pub WorldPos);
 
@@ -38,62 +41,948 @@ impl Default for Orientation {
 pub struct Vision(pub f32);
 
 pub struct Movement {
-    speed: f32,
+    /// Blocks per second
+    pub speed: f32,
+    /// Blocks per second, up is positive
+    pub vertical_velocity: f32,
+}
+
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Health {
+    pub fn full(max: f32) -> Self {
+        Self { current: max, max }
+    }
 }
 
 /// Reach distance
 pub struct Reach(pub f32);
 
+/// What a mob is up to, moved on by [`crate::behaviour::next_behaviour`]
+#[derive(Debug, Clone)]
 pub enum Behaviour {
-    Idle,
+    /// Standing around for this many more seconds
+    Idle(f32),
+    /// Heading for somewhere nearby
     Wandering(WorldPos),
-    Persuing(EntityId),
+    Persuing(Entity),
+    Fleeing(Entity),
 }
 
-#[derive(Default)]
-pub struct Container {
+/// A mob's working memory for getting where its behaviour wants it to go
+#[derive(Debug, Clone)]
+pub struct Brain {
+    /// Every random choice comes from here, so the same seed always plays out the same
+    pub rng: SmallRng,
+    /// Block being headed for
+    pub goal: Option<BlockPos>,
+    /// Blocks left to walk through to get to the goal
+    pub path: Vec<BlockPos>,
+    /// Couldn't find a way to the goal
+    pub stuck: bool,
+    /// Seconds before looking for a way again after getting stuck
+    pub retry_in: f32,
+}
+
+impl Default for Brain {
+    fn default() -> Self {
+        Self::new(SmallRng::from_rng(&mut rand::rng()))
+    }
+}
+
+impl Brain {
+    pub fn new(rng: SmallRng) -> Self {
+        Self {
+            rng,
+            goal: None,
+            path: vec![],
+            stuck: false,
+            retry_in: 0.,
+        }
+    }
+
+    pub fn seeded(seed: u64) -> Self {
+        Self::new(SmallRng::seed_from_u64(seed))
+    }
+}
+
+/// Limits on what a container can hold. None == unlimited
+#[derive(Debug, Clone, Default)]
+pub struct Capacity {
+    /// Total weight of all items held
+    pub max_weight: Option<f32>,
+    /// Number of stacks held, unique items take a slot each
+    pub max_slots: Option<usize>,
+    /// Number of stackable items which fit in one slot
+    pub max_stack: Option<usize>,
+}
+
+#[derive(Default, Clone)]
+pub struct Inventory {
+    /// Number of each item held, including unique items
     pub items: EnumMap<ItemType, usize>,
+    /// Per-instance data for non-stackable items
+    pub instances: Vec<ItemInstance>,
+    pub capacity: Capacity,
 }
 
-impl Container {
-    pub fn add_item(&mut self, item: ItemType, count: usize) {
-        self.items[item] += count;
+impl Inventory {
+    /// Leeway on the weight limit, so rounding errors in summed up weights don't cost a whole item
+    const WEIGHT_EPSILON: f32 = 1e-4;
+
+    /// Create an empty container with limited space
+    pub fn with_capacity(capacity: Capacity) -> Self {
+        Self {
+            capacity,
+            ..Default::default()
+        }
+    }
+
+    /// Total weight of everything held
+    pub fn weight(&self) -> f32 {
+        self.items
+            .iter()
+            .map(|(item, count)| item.data().weight * *count as f32)
+            .sum()
+    }
+
+    /// Number of slots taken up by an item
+    fn slots_for(&self, item: ItemType, count: usize) -> usize {
+        if !item.is_stackable() {
+            return count;
+        }
+
+        match self.capacity.max_stack {
+            Some(max_stack) => count.div_ceil(max_stack),
+            None => count.min(1),
+        }
+    }
+
+    /// Most of an item which fits in one slot
+    pub fn stack_size(&self, item: ItemType) -> usize {
+        if !item.is_stackable() {
+            return 1;
+        }
+
+        self.capacity.max_stack.unwrap_or(usize::MAX)
+    }
+
+    /// Number of slots taken up by everything held
+    pub fn used_slots(&self) -> usize {
+        self.items
+            .iter()
+            .map(|(item, count)| self.slots_for(item, *count))
+            .sum()
+    }
+
+    /// How many more of an item will fit
+    pub fn space_for(&self, item: ItemType) -> usize {
+        let mut space = usize::MAX;
+
+        if let Some(max_weight) = self.capacity.max_weight {
+            let weight = item.data().weight;
+            if weight > 0. {
+                let free = (max_weight - self.weight()).max(0.);
+                space = space.min(((free + Self::WEIGHT_EPSILON) / weight) as usize);
+            }
+        }
+
+        if let Some(max_slots) = self.capacity.max_slots {
+            let free_slots = max_slots.saturating_sub(self.used_slots());
+            let held = self.items[item];
+
+            let slot_space = if !item.is_stackable() {
+                free_slots
+            } else if let Some(max_stack) = self.capacity.max_stack {
+                // Top up the last partial stack before starting new ones
+                let partial = (max_stack - held % max_stack) % max_stack;
+                free_slots.saturating_mul(max_stack).saturating_add(partial)
+            } else if held > 0 || free_slots > 0 {
+                usize::MAX
+            } else {
+                0
+            };
+            space = space.min(slot_space);
+        }
+
+        space
+    }
+
+    /// Add fresh items to the container, as many as will fit. Returns the number which didn't fit.
+    pub fn add_item(&mut self, item: ItemType, count: usize) -> usize {
+        let added = count.min(self.space_for(item));
+        self.items[item] += added;
+
+        if !item.is_stackable() {
+            (0..added).for_each(|_| self.instances.push(ItemInstance::new(item)));
+        }
+
+        count - added
     }
 
     pub fn remove_item(&mut self, item: ItemType, count: usize) {
+        self.take_items(item, count);
+    }
+
+    /// Copy of the items `take_items` would give, without taking them
+    pub fn peek_items(&self, item: ItemType, count: usize) -> Vec<ItemStack> {
+        assert!(self.items[item] >= count, "Not enough items!");
+
+        if item.is_stackable() {
+            return vec![ItemStack::Counted(item, count)];
+        }
+
+        self.instances
+            .iter()
+            .rev()
+            .filter(|i| i.item_type == item)
+            .take(count)
+            .map(|i| ItemStack::Unique(i.clone()))
+            .collect()
+    }
+
+    /// Take items out of the container, keeping any per-instance data.
+    /// Unique items are taken newest-first so the one in use stays put.
+    pub fn take_items(&mut self, item: ItemType, count: usize) -> Vec<ItemStack> {
         assert!(self.items[item] >= count, "Not enough items!");
 
         self.items[item] -= count;
+
+        if item.is_stackable() {
+            return vec![ItemStack::Counted(item, count)];
+        }
+
+        let mut stacks = vec![];
+        for _ in 0..count {
+            let index = self
+                .instances
+                .iter()
+                .rposition(|i| i.item_type == item)
+                .expect("Item count out of sync with instances!");
+            stacks.push(ItemStack::Unique(self.instances.remove(index)));
+        }
+
+        stacks
+    }
+
+    /// Put a stack of items into the container. Check there's space for it first.
+    pub fn insert_stack(&mut self, stack: ItemStack) {
+        self.items[stack.item_type()] += stack.count();
+
+        if let ItemStack::Unique(instance) = stack {
+            self.instances.push(instance);
+        }
+    }
+
+    /// Iterate over the unique instances of an item
+    pub fn instances_of(&self, item: ItemType) -> impl Iterator<Item = &ItemInstance> {
+        self.instances.iter().filter(move |i| i.item_type == item)
+    }
+
+    /// Get the instance of an item which would be used first
+    pub fn active_instance(&self, item: ItemType) -> Option<&ItemInstance> {
+        self.instances_of(item).next()
+    }
+
+    /// Wear down the instance of an item in use, removing it if it breaks.
+    /// Returns true if the item broke.
+    pub fn wear_item(&mut self, item: ItemType, amount: u32) -> bool {
+        let Some(index) = self.instances.iter().position(|i| i.item_type == item) else {
+            return false;
+        };
+
+        let broken = self.instances[index].wear(amount);
+        if broken {
+            self.instances.remove(index);
+            self.items[item] -= 1;
+        }
+
+        broken
     }
 
     /// Get the recipes the player can currently craft based on what they have on them
     pub fn get_craftable_recipes(&mut self) -> impl Iterator<Item = &'static Recipe> {
-        RECIPES.iter().filter(|r| {
-            r.inputs
-                .iter()
-                .all(|(item, count)| self.items[*item] >= *count)
-        })
+        RECIPES
+            .iter()
+            .filter(|r| r.can_craft(&self.items) && self.has_room_for_outputs(r))
+    }
+
+    /// Whether everything a recipe could produce would fit once its inputs are used up
+    pub fn has_room_for_outputs(&self, recipe: &Recipe) -> bool {
+        let inputs = recipe.inputs.iter().map(|(item, count)| (*item, *count));
+        self.can_swap(inputs, &recipe.most_outputs())
     }
 
-    /// Craft the given recipe. panics if the player doesn't have eough ingredients
-    pub fn craft_recipe(&mut self, recipe: &Recipe) {
-        // Remove input items
-        recipe.inputs.iter().for_each(|(item, count)| {
-            self.remove_item(*item, *count);
+    /// Whether all of the given items would fit at once
+    pub fn can_fit(&self, items: &[(ItemType, usize)]) -> bool {
+        self.can_swap([], items)
+    }
+
+    /// Whether all of the `added` items would fit at once after taking out the `removed` ones.
+    /// Only the counts are looked at, so it's cheap enough to check every frame.
+    pub fn can_swap(
+        &self,
+        removed: impl IntoIterator<Item = (ItemType, usize)>,
+        added: &[(ItemType, usize)],
+    ) -> bool {
+        let mut items = self.items;
+        removed.into_iter().for_each(|(item, count)| {
+            items[item] = items[item].saturating_sub(count);
         });
+        added.iter().for_each(|(item, count)| items[*item] += count);
+
+        let weight = items
+            .iter()
+            .map(|(item, count)| item.data().weight * *count as f32)
+            .sum::<f32>();
+        let slots = items
+            .iter()
+            .map(|(item, count)| self.slots_for(item, *count))
+            .sum::<usize>();
+
+        self.capacity
+            .max_weight
+            .is_none_or(|max| weight <= max + Self::WEIGHT_EPSILON)
+            && self.capacity.max_slots.is_none_or(|max| slots <= max)
+    }
+
+    /// Craft the given recipe. panics if the player doesn't have eough ingredients.
+    /// Outputs which don't fit are lost, so check `has_room_for_outputs` first.
+    pub fn craft_recipe(&mut self, recipe: &Recipe, rng: &mut impl Rng) {
+        let (consumed, byproducts) = recipe.roll(rng);
+
+        self.remove_inputs(recipe, consumed);
+        self.add_outputs(recipe, byproducts);
+    }
+
+    /// Use up the inputs for one craft, along with any catalysts rolled as consumed
+    pub fn remove_inputs(&mut self, recipe: &Recipe, consumed: Vec<(ItemType, usize)>) {
+        recipe
+            .inputs
+            .iter()
+            .map(|(item, count)| (*item, *count))
+            .chain(consumed)
+            .for_each(|(item, count)| {
+                self.remove_item(item, count);
+            });
+    }
+
+    /// Add the products of one craft, along with any byproducts rolled. Anything which doesn't fit
+    /// is lost.
+    pub fn add_outputs(&mut self, recipe: &Recipe, byproducts: Vec<(ItemType, usize)>) {
+        recipe
+            .outputs
+            .iter()
+            .cloned()
+            .chain(byproducts)
+            .for_each(|(item, count)| {
+                let overflow = self.add_item(item, count);
+                if overflow > 0 {
+                    log::warn!("No room for {overflow} {item:?} from crafting, lost!");
+                }
+            });
+    }
+}
+
+/// Crafting progress made by an entity, used to unlock recipes
+#[derive(Default)]
+pub struct Progression {
+    /// Items which have been held at some point
+    pub discovered: EnumMap<ItemType, bool>,
+    /// Number of times each item has been crafted by hand, however many each craft makes
+    pub crafted: EnumMap<ItemType, usize>,
+}
+
+impl Progression {
+    pub fn is_met(&self, condition: &UnlockCondition) -> bool {
+        match condition {
+            UnlockCondition::Discovered(item) => self.discovered[*item],
+            UnlockCondition::Crafted(item, count) => self.crafted[*item] >= *count,
+        }
+    }
+
+    /// Whether the recipe can be crafted by hand
+    pub fn is_unlocked(&self, recipe: &Recipe) -> bool {
+        recipe.unlock.iter().all(|c| self.is_met(c))
+    }
 
-        // Add output items
-        self.add_item(recipe.output.0, recipe.output.1);
+    /// Whether the recipe can be set in a crafter
+    pub fn can_automate(&self, recipe: &Recipe) -> bool {
+        self.is_unlocked(recipe) && self.crafted[recipe.output().0] >= recipe.automation_crafts
+    }
+
+    /// Mark everything in the container as discovered
+    pub fn discover(&mut self, container: &Inventory) {
+        container
+            .items
+            .iter()
+            .filter(|(_, count)| **count > 0)
+            .for_each(|(item, _)| self.discovered[item] = true);
+    }
+
+    /// Record a recipe being crafted by hand once
+    pub fn record_craft(&mut self, recipe: &Recipe) {
+        let (item, _) = recipe.output();
+        self.crafted[item] += 1;
     }
 }
 
+/// Progress made breaking a block while the mine button is held
 #[derive(Default)]
+pub struct Mining {
+    /// Whether the mine button is held down
+    pub active: bool,
+    /// Block currently being mined
+    pub target: Option<BlockPos>,
+    /// Fraction of the way to breaking the target
+    pub progress: f32,
+}
+
+impl Mining {
+    /// Forget about the current target & any progress on it
+    pub fn reset(&mut self) {
+        self.target = None;
+        self.progress = 0.;
+    }
+}
+
+/// How long a crafting job keeps going for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobTarget {
+    /// Craft this many more times
+    Repeat(usize),
+    /// Keep the output buffer stocked with this many of the main product
+    Stock(usize),
+}
+
+/// Recipe waiting in a crafter's queue
+#[derive(Debug, Clone)]
+pub struct CraftingJob {
+    pub recipe: Recipe,
+    pub target: JobTarget,
+}
+
+impl CraftingJob {
+    /// Whether the job still wants crafting, given what's already been made
+    pub fn wants_more(&self, output: &Inventory) -> bool {
+        match self.target {
+            JobTarget::Repeat(count) => count > 0,
+            JobTarget::Stock(count) => output.items[self.recipe.output().0] < count,
+        }
+    }
+
+    /// Number of crafts before the job stops wanting more
+    pub fn crafts_left(&self, output: &Inventory) -> usize {
+        match self.target {
+            JobTarget::Repeat(count) => count,
+            JobTarget::Stock(count) => {
+                let (item, per_craft) = self.recipe.output();
+                count.saturating_sub(output.items[item]).div_ceil(per_craft)
+            }
+        }
+    }
+}
+
+/// Change to a crafter's job queue
+#[derive(Debug, Clone)]
+pub enum CrafterUpdate {
+    /// Add a job to the back of the queue
+    Queue(CraftingJob),
+    /// Change how long the job at an index runs for
+    SetTarget(usize, JobTarget),
+    /// Swap the job at an index with the one in front of it
+    MoveUp(usize),
+    Remove(usize),
+    SetPaused(bool),
+}
+
+#[derive(Clone)]
 pub struct Crafter {
-    pub recipe: Option<Recipe>,
+    /// Which recipes it makes, crafters craft while other machines eg. crush ores
+    pub category: RecipeCategory,
+    /// Jobs are worked on in order, stocked-up jobs are skipped until they run low
+    pub queue: Vec<CraftingJob>,
+    pub paused: bool,
     pub crafting_juice: f32,
+    /// Most juice drawn from the power network
     pub juice_per_second: f32,
+    /// Fraction of the juice wanted which the network supplied last tick
+    pub power: f32,
+    /// Used for byproduct & catalyst rolls
+    pub rng: SmallRng,
+    /// Ingredients & catalysts waiting to be used
+    pub input: Inventory,
+    /// Finished products waiting to be taken out
+    pub output: Inventory,
+}
+
+impl Default for Crafter {
+    fn default() -> Self {
+        Self {
+            category: RecipeCategory::Crafting,
+            queue: vec![],
+            paused: false,
+            crafting_juice: 0.,
+            juice_per_second: 0.,
+            power: 0.,
+            rng: SmallRng::from_rng(&mut rand::rng()),
+            input: Inventory::default(),
+            output: Inventory::default(),
+        }
+    }
+}
+
+impl Crafter {
+    /// Seconds between crafting ticks
+    pub const TICK_INTERVAL: f32 = 0.05;
+
+    /// Index of the job being worked on, if any
+    pub fn active_job(&self) -> Option<usize> {
+        if self.paused {
+            return None;
+        }
+
+        self.queue
+            .iter()
+            .position(|job| job.wants_more(&self.output))
+    }
+
+    /// Recipe of the job being worked on
+    pub fn recipe(&self) -> Option<&Recipe> {
+        self.active_job().map(|i| &self.queue[i].recipe)
+    }
+
+    /// Whether the ingredients are in & there's room for the result, byproducts included
+    pub fn can_craft(&self, recipe: &Recipe) -> bool {
+        recipe.can_craft(&self.input.items) && self.output.can_fit(&recipe.most_outputs())
+    }
+
+    /// Juice per second wanted from the power network, nothing while idle
+    pub fn demand(&self) -> f32 {
+        match self.recipe() {
+            Some(recipe) if self.can_craft(recipe) => self.juice_per_second,
+            _ => 0.,
+        }
+    }
+
+    /// Craft the active job once, moving from the input buffer to the output buffer
+    pub fn craft(&mut self) {
+        let Some(index) = self.active_job() else {
+            return;
+        };
+        let job = &mut self.queue[index];
+
+        let (consumed, byproducts) = job.recipe.roll(&mut self.rng);
+        self.input.remove_inputs(&job.recipe, consumed);
+        self.output.add_outputs(&job.recipe, byproducts);
+
+        // Finished jobs leave the queue, stock jobs stay to top up later
+        if let JobTarget::Repeat(count) = &mut job.target {
+            *count -= 1;
+            if *count == 0 {
+                self.queue.remove(index);
+            }
+        }
+    }
+
+    /// Change the job queue
+    pub fn update(&mut self, update: &CrafterUpdate) {
+        let crafting = self.recipe().map(Recipe::output);
+
+        match update {
+            CrafterUpdate::Queue(job) => self.queue.push(job.clone()),
+            CrafterUpdate::SetTarget(index, target) => {
+                if let Some(job) = self.queue.get_mut(*index) {
+                    job.target = *target;
+                }
+            }
+            CrafterUpdate::MoveUp(index) => {
+                if (1..self.queue.len()).contains(index) {
+                    self.queue.swap(*index - 1, *index);
+                }
+            }
+            CrafterUpdate::Remove(index) => {
+                if *index < self.queue.len() {
+                    self.queue.remove(*index);
+                }
+            }
+            CrafterUpdate::SetPaused(paused) => self.paused = *paused,
+        }
+
+        // Progress doesn't carry over to a different recipe
+        if self.recipe().map(Recipe::output) != crafting {
+            self.crafting_juice = 0.;
+        }
+    }
 }
 
+/// Moves items from the block on one side to the block on another
+#[derive(Debug, Clone)]
+pub struct Hopper {
+    /// Side items are pulled in from
+    pub from: Side,
+    /// Side items are pushed out of
+    pub to: Side,
+    /// Only these items are moved. Empty == move anything
+    pub filter: Vec<ItemType>,
+    /// Seconds between moves
+    pub interval: f32,
+    /// Most items moved at once
+    pub stack_size: usize,
+    /// Time since the last move
+    pub timer: f32,
+}
+
+impl Default for Hopper {
+    fn default() -> Self {
+        Self {
+            from: Side::Up,
+            to: Side::Down,
+            filter: vec![],
+            interval: 0.5,
+            stack_size: 1,
+            timer: 0.,
+        }
+    }
+}
+
+impl Hopper {
+    /// Whether the filter lets an item through
+    pub fn allows(&self, item: ItemType) -> bool {
+        self.filter.is_empty() || self.filter.contains(&item)
+    }
+}
+
+/// Something an item filter can pick out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterRule {
+    Item(ItemType),
+    Category(ItemCategory),
+}
+
+impl FilterRule {
+    pub fn matches(&self, item: ItemType) -> bool {
+        match self {
+            FilterRule::Item(rule) => *rule == item,
+            FilterRule::Category(category) => item.category() == *category,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilterMode {
+    /// Only items matching a rule
+    #[default]
+    Whitelist,
+    /// Anything but items matching a rule
+    Blacklist,
+}
+
+/// Limits which items a chest takes, however they're put in
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ItemFilter {
+    pub mode: FilterMode,
+    /// Empty == anything goes
+    pub rules: Vec<FilterRule>,
+    /// Storage terminals fill higher priority chests first, and empty them last
+    pub priority: i32,
+    /// Sorting mode, takes anything but passes items the rules don't allow on out of this side
+    pub sort_to: Option<Side>,
+    /// Time since items were last passed on
+    pub sort_timer: f32,
+}
+
+impl ItemFilter {
+    /// Seconds between sorting chests passing items on
+    pub const SORT_INTERVAL: f32 = 0.5;
+
+    /// Whether the rules allow an item
+    pub fn matches(&self, item: ItemType) -> bool {
+        let listed = self.rules.iter().any(|rule| rule.matches(item));
+        match self.mode {
+            FilterMode::Whitelist => self.rules.is_empty() || listed,
+            FilterMode::Blacklist => !listed,
+        }
+    }
+
+    /// Whether an item can be put in, sorting chests take everything to pass it on
+    pub fn accepts(&self, item: ItemType) -> bool {
+        self.sort_to.is_some() || self.matches(item)
+    }
+}
+
+/// Burns fuel items one at a time to make heat
+#[derive(Clone)]
+pub struct Burner {
+    /// Items waiting to be burnt
+    pub fuel: Inventory,
+    /// Seconds left on the item being burnt
+    pub burn_time: f32,
+    /// Seconds the item being burnt lasts in total
+    pub burn_length: f32,
+}
+
+impl Default for Burner {
+    fn default() -> Self {
+        Self {
+            fuel: Inventory::with_capacity(Capacity {
+                max_slots: Some(1),
+                max_stack: Some(64),
+                ..Default::default()
+            }),
+            burn_time: 0.,
+            burn_length: 0.,
+        }
+    }
+}
+
+impl Burner {
+    /// Whether something is burning or waiting to be burnt
+    pub fn has_fuel(&self) -> bool {
+        self.burn_time > 0. || self.fuel.items.values().any(|count| *count > 0)
+    }
+
+    /// Fraction of the item being burnt which is left
+    pub fn remaining(&self) -> f32 {
+        if self.burn_length > 0. {
+            (self.burn_time / self.burn_length).clamp(0., 1.)
+        } else {
+            0.
+        }
+    }
+
+    /// Burn for up to some seconds, lighting more fuel as needed.
+    /// Returns the seconds actually burnt, which is less if the fuel runs out.
+    pub fn burn(&mut self, seconds: f32) -> f32 {
+        let mut burnt = 0.;
+
+        while burnt < seconds {
+            if self.burn_time <= 0. {
+                // Light the next bit of fuel
+                let Some(item) = (0..ItemType::LENGTH)
+                    .map(ItemType::from_usize)
+                    .find(|item| self.fuel.items[*item] > 0)
+                else {
+                    break;
+                };
+                self.fuel.take_items(item, 1);
+                self.burn_length = item.data().burn_value.unwrap_or(0.);
+                self.burn_time += self.burn_length;
+                continue;
+            }
+
+            let step = (seconds - burnt).min(self.burn_time);
+            self.burn_time -= step;
+            burnt += step;
+        }
+
+        burnt
+    }
+}
+
+/// Makes crafting juice for the power network it's part of
+#[derive(Clone)]
+pub struct Generator {
+    /// Juice per second made while running
+    pub juice_per_second: f32,
+    /// Fuel burnt to keep running. None == runs without fuel
+    pub burner: Option<Burner>,
+    /// Fraction of its output the network is using, set by the power network
+    pub load: f32,
+}
+
+impl Generator {
+    /// Seconds between burning ticks
+    pub const TICK_INTERVAL: f32 = 0.05;
+
+    /// Generator which burns fuel items
+    pub fn burner(juice_per_second: f32) -> Self {
+        Self {
+            juice_per_second,
+            burner: Some(Burner::default()),
+            load: 0.,
+        }
+    }
+
+    /// Generator which runs forever on its own
+    pub fn passive(juice_per_second: f32) -> Self {
+        Self {
+            juice_per_second,
+            burner: None,
+            load: 0.,
+        }
+    }
+
+    /// Juice per second which could be made right now
+    pub fn supply(&self) -> f32 {
+        match &self.burner {
+            Some(burner) if !burner.has_fuel() => 0.,
+            _ => self.juice_per_second,
+        }
+    }
+}
+
+/// Cooks smelting recipes with heat from burning fuel
+#[derive(Clone)]
+pub struct Furnace {
+    pub burner: Burner,
+    /// Ingredients waiting to be cooked
+    pub input: Inventory,
+    /// Finished products waiting to be taken out
+    pub output: Inventory,
+    /// Recipe being cooked, picked from what's in the input
+    pub recipe: Option<&'static SmeltingRecipe>,
+    /// Seconds of heat put into the current recipe
+    pub cook_time: f32,
+}
+
+impl Default for Furnace {
+    fn default() -> Self {
+        let buffer_capacity = Capacity {
+            max_slots: Some(2),
+            max_stack: Some(64),
+            ..Default::default()
+        };
+
+        Self {
+            burner: Burner::default(),
+            input: Inventory::with_capacity(buffer_capacity.clone()),
+            output: Inventory::with_capacity(buffer_capacity),
+            recipe: None,
+            cook_time: 0.,
+        }
+    }
+}
+
+impl Furnace {
+    /// Seconds between cooking ticks
+    pub const TICK_INTERVAL: f32 = 0.05;
+
+    /// First recipe which can be cooked with the input, with room for the result
+    pub fn smeltable(&self) -> Option<&'static SmeltingRecipe> {
+        SMELTING_RECIPES
+            .iter()
+            .find(|r| r.can_smelt(&self.input.items) && self.output.can_fit(&[r.output]))
+    }
+}
+
+/// Block which does work over time, see `Tickable`.
+/// Blocks which miss ticks catch up on the game time they missed the next time they're ticked.
+#[derive(Debug, Clone)]
+pub struct Ticker {
+    /// Seconds between ticks
+    pub interval: f32,
+    /// Game time of the last tick, None if the clock hasn't started
+    pub last_tick: Option<f64>,
+    /// Idle blocks aren't ticked until something wakes them
+    pub asleep: bool,
+}
+
+impl Ticker {
+    pub fn every(interval: f32) -> Self {
+        Self {
+            interval,
+            last_tick: None,
+            asleep: false,
+        }
+    }
+
+    /// Start ticking again, nothing happened while asleep so there's nothing to catch up on
+    pub fn wake(&mut self) {
+        if self.asleep {
+            self.asleep = false;
+            self.last_tick = None;
+        }
+    }
+}
+
+/// Keeps the chunks around it loaded, so machines there keep running while the player's away
+#[derive(Debug, Clone, Default)]
+pub struct ChunkLoader {
+    /// Why it isn't keeping anything loaded, if it isn't
+    pub error: Option<ForceLoadError>,
+}
+
+impl ChunkLoader {
+    /// Chunks away from its own which are kept loaded
+    pub const RADIUS: u32 = 1;
+
+    /// Chunks kept loaded by a loader at a position
+    pub fn chunks(pos: &BlockPos) -> Vec<ChunkPos> {
+        let (chunk, _) = pos.to_chunk_offset();
+        chunk.chunks_within(Self::RADIUS).collect()
+    }
+}
+
+/// Block which is part of a power network
+#[derive(Debug, Clone, Default)]
+pub struct PowerNode(pub NetworkStatus);
+
+/// Block which is part of a storage network, joining up with any touching it
+#[derive(Debug, Clone, Default)]
+pub struct StorageNode;
+
+/// Gets at the items in every chest on its storage network
+#[derive(Clone, Default)]
+pub struct StorageTerminal {
+    /// Chests on the network, in no particular order
+    pub chests: Vec<Entity>,
+    /// Everything held across all of them
+    pub contents: Inventory,
+}
+
+/// Belt which carries item entities along in one direction
+#[derive(Clone)]
+pub struct Conveyor {
+    /// Side items move out of
+    pub direction: Side,
+    /// Items waiting to be put on the belt
+    pub input: Inventory,
+}
+
+impl Default for Conveyor {
+    fn default() -> Self {
+        Self {
+            direction: Side::North,
+            input: Inventory::with_capacity(Capacity {
+                max_slots: Some(1),
+                max_stack: Some(4),
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+impl Conveyor {
+    /// Blocks per second items are carried
+    pub const SPEED: f32 = 1.5;
+    /// Width of an item riding a belt
+    pub const ITEM_SIZE: f32 = 0.25;
+    /// Closest items get to each other along a belt
+    pub const ITEM_SPACING: f32 = 0.35;
+
+    /// Centre of an item riding in the middle of the belt at `pos`
+    pub fn item_centre(pos: &BlockPos) -> WorldPos {
+        WorldPos(Point3::new(
+            pos.0.x as f32 + 0.5,
+            pos.0.y as f32 + 1. + Self::ITEM_SIZE / 2.,
+            pos.0.z as f32 + 0.5,
+        ))
+    }
+
+    /// Block an item would be riding on
+    pub fn under(pos: &WorldPos) -> BlockPos {
+        (pos - Vector3::unit_y()).to_block_pos()
+    }
+}
+
+/// Item lying loose in the world, eg. riding a conveyor
+#[derive(Debug, Clone)]
+pub struct ItemEntity(pub ItemStack);
+
 #[derive(Clone, Debug)]
 pub struct UprightOrientation {
     pub yaw: Rad<f32>,
@@ -119,6 +1008,95 @@ impl UprightOrientation {
 pub enum UIType {
     Chest,
     Crafter,
+    Hopper,
+    Conveyor,
+    Generator,
+    Conduit,
+    Furnace,
+    ChunkLoader,
+    StorageTerminal,
+}
+
+/// Order items are listed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ItemSort {
+    /// Grouped by category, in the order items are defined
+    #[default]
+    Category,
+    Name,
+    /// Most held first
+    Count,
+    /// Heaviest total first
+    Weight,
+}
+
+impl ItemSort {
+    pub const ALL: [ItemSort; 4] = [
+        ItemSort::Category,
+        ItemSort::Name,
+        ItemSort::Count,
+        ItemSort::Weight,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ItemSort::Category => "Category",
+            ItemSort::Name => "Name",
+            ItemSort::Count => "Count",
+            ItemSort::Weight => "Weight",
+        }
+    }
+}
+
+/// Search, sort & category picked for an item window
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ItemView {
+    pub search: String,
+    pub sort: ItemSort,
+    /// None == every category
+    pub category: Option<ItemCategory>,
+}
+
+impl ItemView {
+    /// Items held which match the search & category, in sorted order
+    pub fn apply(&self, items: &EnumMap<ItemType, usize>) -> Vec<(ItemType, usize)> {
+        let search = self.search.to_lowercase();
+        let mut shown = items
+            .iter()
+            .filter(|(item, count)| {
+                **count > 0
+                    && self
+                        .category
+                        .is_none_or(|category| item.category() == category)
+                    && item.data().name.to_lowercase().contains(&search)
+            })
+            .map(|(item, count)| (item, *count))
+            .collect::<Vec<_>>();
+
+        // Stable sorts, so ties stay in the order items are defined
+        match self.sort {
+            ItemSort::Category => shown.sort_by_key(|(item, _)| item.category().into_usize()),
+            ItemSort::Name => shown.sort_by_key(|(item, _)| item.data().name),
+            ItemSort::Count => shown.sort_by_key(|(_, count)| std::cmp::Reverse(*count)),
+            ItemSort::Weight => shown.sort_by(|(a, a_count), (b, b_count)| {
+                let a = a.data().weight * *a_count as f32;
+                let b = b.data().weight * *b_count as f32;
+                b.total_cmp(&a)
+            }),
+        }
+
+        shown
+    }
+}
+
+/// A player's view settings for each item window, by window name
+#[derive(Debug, Default)]
+pub struct ItemViews(pub FxHashMap<String, ItemView>);
+
+impl ItemViews {
+    pub fn get(&self, window: &str) -> ItemView {
+        self.0.get(window).cloned().unwrap_or_default()
+    }
 }
 
 #[derive(Default)]
@@ -148,3 +1126,197 @@ impl Hotbar {
         *self.slots.get_mut(slot).expect("Slot out of range") = Some(item);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rustc_hash::FxHashMap;
+
+    use crate::{
+        data::{
+            item::{ItemCategory, ItemType},
+            recipe::{Byproduct, Recipe, UnlockCondition},
+        },
+        entity::components::{
+            Capacity, Crafter, CrafterUpdate, CraftingJob, Inventory, ItemSort, ItemView,
+            JobTarget, Progression,
+        },
+    };
+
+    fn dark_stone() -> Recipe {
+        Recipe {
+            inputs: FxHashMap::from_iter([(ItemType::Stone, 1)]),
+            outputs: vec![(ItemType::DarkStone, 1)],
+            byproducts: vec![],
+            catalysts: vec![],
+            crafting_juice_cost: 1.,
+            unlock: vec![],
+            automation_crafts: 0,
+        }
+    }
+
+    #[test]
+    fn test_job_queue() {
+        let mut crafter = Crafter::default();
+        crafter.input.add_item(ItemType::Stone, 10);
+
+        crafter.update(&CrafterUpdate::Queue(CraftingJob {
+            recipe: dark_stone(),
+            target: JobTarget::Stock(2),
+        }));
+        crafter.update(&CrafterUpdate::Queue(CraftingJob {
+            recipe: dark_stone(),
+            target: JobTarget::Repeat(3),
+        }));
+
+        // Stock job runs until the output holds enough
+        (0..2).for_each(|_| crafter.craft());
+        assert_eq!(crafter.active_job(), Some(1));
+
+        // Repeat job leaves the queue once done
+        (0..3).for_each(|_| crafter.craft());
+        assert_eq!(crafter.queue.len(), 1);
+        assert_eq!(crafter.output.items[ItemType::DarkStone], 5);
+        assert_eq!(crafter.active_job(), None);
+
+        // Stock job tops back up when products are taken out
+        crafter.output.remove_item(ItemType::DarkStone, 5);
+        assert_eq!(crafter.active_job(), Some(0));
+
+        crafter.update(&CrafterUpdate::SetPaused(true));
+        crafter.craft();
+        assert_eq!(crafter.output.items[ItemType::DarkStone], 0);
+    }
+
+    #[test]
+    fn test_room_for_byproducts() {
+        let recipe = Recipe {
+            byproducts: vec![Byproduct {
+                item: ItemType::Slag,
+                count: 1,
+                chance: 0.5,
+            }],
+            ..dark_stone()
+        };
+        let slots = |max_slots| Capacity {
+            max_slots: Some(max_slots),
+            ..Default::default()
+        };
+
+        // The stone's slot is freed up, but the slag might need another
+        let mut inventory = Inventory::with_capacity(slots(1));
+        inventory.add_item(ItemType::Stone, 1);
+        assert!(inventory.has_room_for_outputs(&dark_stone()));
+        assert!(!inventory.has_room_for_outputs(&recipe));
+        inventory.capacity = slots(2);
+        assert!(inventory.has_room_for_outputs(&recipe));
+
+        let mut crafter = Crafter {
+            output: Inventory::with_capacity(slots(1)),
+            ..Default::default()
+        };
+        crafter.input.add_item(ItemType::Stone, 1);
+        assert!(crafter.can_craft(&dark_stone()));
+        assert!(!crafter.can_craft(&recipe));
+    }
+
+    #[test]
+    fn test_progression() {
+        let recipe = Recipe {
+            unlock: vec![
+                UnlockCondition::Discovered(ItemType::Stone),
+                UnlockCondition::Crafted(ItemType::Dirt, 1),
+            ],
+            automation_crafts: 2,
+            ..dark_stone()
+        };
+        let mut progression = Progression::default();
+        assert!(!progression.is_met(&recipe.unlock[0]));
+
+        let mut inventory = Inventory::default();
+        inventory.add_item(ItemType::Stone, 1);
+        progression.discover(&inventory);
+        assert!(progression.is_met(&recipe.unlock[0]));
+        assert!(!progression.is_unlocked(&recipe));
+
+        progression.crafted[ItemType::Dirt] = 1;
+        assert!(progression.is_unlocked(&recipe));
+        assert!(!progression.can_automate(&recipe));
+
+        // Crafts are counted, not the items they make
+        let double = Recipe {
+            outputs: vec![(ItemType::DarkStone, 2)],
+            ..recipe.clone()
+        };
+        progression.record_craft(&double);
+        assert_eq!(progression.crafted[ItemType::DarkStone], 1);
+        assert!(!progression.can_automate(&recipe));
+        progression.record_craft(&recipe);
+        assert!(progression.can_automate(&recipe));
+    }
+
+    #[test]
+    fn test_item_view() {
+        let mut inventory = Inventory::default();
+        inventory.add_item(ItemType::Stone, 3);
+        inventory.add_item(ItemType::Copper, 10);
+        inventory.add_item(ItemType::Chest, 1);
+        inventory.add_item(ItemType::CopperPickaxe, 1);
+
+        let shown = |view: &ItemView| {
+            view.apply(&inventory.items)
+                .into_iter()
+                .map(|(item, _)| item)
+                .collect::<Vec<_>>()
+        };
+
+        let mut view = ItemView::default();
+        assert_eq!(
+            shown(&view),
+            vec![
+                ItemType::Stone,
+                ItemType::Copper,
+                ItemType::CopperPickaxe,
+                ItemType::Chest
+            ]
+        );
+
+        view.sort = ItemSort::Count;
+        assert_eq!(shown(&view)[0], ItemType::Copper);
+
+        view.search = "copper".to_string();
+        assert_eq!(
+            shown(&view),
+            vec![ItemType::Copper, ItemType::CopperPickaxe]
+        );
+
+        view.category = Some(ItemCategory::Tools);
+        assert_eq!(shown(&view), vec![ItemType::CopperPickaxe]);
+    }
+
+    #[test]
+    fn test_container_capacity() {
+        let mut container = Inventory::with_capacity(Capacity {
+            max_weight: None,
+            max_slots: Some(2),
+            max_stack: Some(10),
+        });
+
+        // Stackable items fill up whole slots
+        assert_eq!(container.add_item(ItemType::Stone, 15), 0);
+        assert_eq!(container.used_slots(), 2);
+        assert_eq!(container.add_item(ItemType::Stone, 10), 5);
+        assert_eq!(container.items[ItemType::Stone], 20);
+
+        // No slots left for anything else
+        assert_eq!(container.add_item(ItemType::CopperPickaxe, 1), 1);
+        assert!(container.instances.is_empty());
+
+        // Weight limits apply on top
+        let mut container = Inventory::with_capacity(Capacity {
+            max_weight: Some(ItemType::Stone.data().weight * 3.),
+            ..Default::default()
+        });
+        assert_eq!(container.add_item(ItemType::Stone, 5), 2);
+        assert_eq!(container.space_for(ItemType::Stone), 0);
+    }
+}
@@ -2,72 +2,475 @@ pub mod ui;
 
 use std::time::Duration;
 
-use cgmath::{Point3, Rotation, Vector3};
+use cgmath::{Deg, InnerSpace, MetricSpace, Point3, Quaternion, Rotation3, Vector3, Zero};
+use enum_map::Enum;
 use hecs::{Entity, EntityBuilder, World};
+use rustc_hash::{FxHashMap, FxHashSet};
 
 use crate::{
     InteractionMode,
+    container::{Container, Filtered, Side, TransferError},
     data::{
         block::BlockType,
+        entity::EntityType,
         item::ItemType,
         loader::{BLOCKS, ITEMS},
     },
-    entity::components::{
-        Container, Crafter, Hotbar, Orientation, Position, Reach, UprightOrientation, Vision,
+    entity::{
+        bundles::BlockStates,
+        components::{
+            Brain, Capacity, Conveyor, Crafter, Furnace, Generator, Health, Hopper, Hotbar,
+            Inventory, ItemEntity, ItemFilter, ItemViews, Mining, Movement, Orientation, Position,
+            PowerNode, Progression, Reach, Ticker, UprightOrientation, Vision,
+        },
     },
     event::messages::TransferItemMessage,
+    item::{ItemStack, MiningCapability},
     math::bbox::AABB,
-    state::world::{BlockPos, WorldPos},
+    power::{NetworkStatus, PowerGrid},
+    state::world::{BlockPos, ChunkPos, WorldPos},
+    tick::{TickScheduler, TickState, wake, with_tickable},
 };
 
-pub trait System {
-    fn tick(eccs: &mut World, duration: &Duration);
+/// Tick the blocks which work over time, skipping any which are asleep or not due yet.
+/// Anything which misses ticks makes up for it the next time it's ticked.
+pub fn block_tick(
+    ecs: &mut World,
+    scheduler: &mut TickScheduler,
+    active_chunks: &FxHashSet<ChunkPos>,
+    duration: &Duration,
+) {
+    scheduler.now += duration.as_secs_f64();
+    let now = scheduler.now;
+
+    let due = ecs
+        .query_mut::<(&mut Ticker, Option<&BlockPos>)>()
+        .into_iter()
+        .filter(|(_, (ticker, pos))| {
+            !ticker.asleep && pos.is_none_or(|pos| is_active(active_chunks, pos))
+        })
+        .filter_map(|(entity, (ticker, _))| {
+            // New & freshly woken blocks start counting from now
+            let last_tick = *ticker.last_tick.get_or_insert(now);
+            (now - last_tick >= ticker.interval as f64).then_some(entity)
+        })
+        .collect();
+
+    for entity in scheduler.turns(due) {
+        let mut ticker = ecs.get::<&mut Ticker>(entity).unwrap();
+        let elapsed = (now - ticker.last_tick.unwrap_or(now)) as f32;
+        let state = with_tickable(ecs, entity, |tickable| tickable.tick(elapsed));
+
+        ticker.last_tick = Some(now);
+        ticker.asleep = state != Some(TickState::Busy);
+    }
 }
 
-pub struct MoveSystem;
+/// Share the juice generators make between the consumers on each power network
+pub fn power_tick(ecs: &mut World, grid: &mut PowerGrid, active_chunks: &FxHashSet<ChunkPos>) {
+    // Anything no longer on a network goes without
+    for (_, crafter) in ecs.query_mut::<&mut Crafter>() {
+        crafter.power = 0.;
+    }
+
+    for network in grid.networks(ecs) {
+        // Blocks outside the active area are frozen, so they neither use nor make power
+        let network = network
+            .iter()
+            .filter(|entity| {
+                ecs.get::<&BlockPos>(**entity)
+                    .is_ok_and(|pos| is_active(active_chunks, &pos))
+            })
+            .collect::<Vec<_>>();
 
-impl System for MoveSystem {
-    fn tick(ecs: &mut World, duration: &Duration) {
-        for (_, (position, orientation)) in ecs.query_mut::<(&mut Position, &mut Orientation)>() {
-            let facing = orientation.0.rotate_vector(Vector3::unit_z());
-            let movement_speed = 0.1 * duration.as_secs_f32();
+        let mut status = NetworkStatus {
+            blocks: network.len(),
+            ..Default::default()
+        };
+        for entity in &network {
+            if let Ok(generator) = ecs.get::<&Generator>(**entity) {
+                status.supply += generator.supply();
+            }
+            if let Ok(crafter) = ecs.get::<&Crafter>(**entity) {
+                status.demand += crafter.demand();
+            }
+        }
 
-            position.0.0 += facing * movement_speed;
+        // Generators only burn as hard as they need to, consumers slow down when supply is short
+        for entity in network {
+            if let Ok(mut generator) = ecs.get::<&mut Generator>(*entity) {
+                generator.load = status.load();
+            }
+            if let Ok(mut crafter) = ecs.get::<&mut Crafter>(*entity) {
+                crafter.power = status.satisfaction();
+            }
+            if let Ok(mut node) = ecs.get::<&mut PowerNode>(*entity) {
+                node.0 = status;
+            }
         }
     }
 }
 
-/// Tick all crafters
-pub fn crafting_tick(ecs: &mut World, duration: &Duration) {
-    for (_, (container, crafter)) in ecs.query_mut::<(&mut Container, &mut Crafter)>() {
-        // Only process when we've got a recipe
-        let Some(recipe) = &crafter.recipe else {
-            return;
+/// Work out the items to move between the blocks either side of each hopper, once per interval.
+/// The transfers are returned rather than done, so they can go through the message queue.
+pub fn hopper_tick(
+    ecs: &World,
+    block_states: &BlockStates,
+    active_chunks: &FxHashSet<ChunkPos>,
+    duration: &Duration,
+) -> Vec<TransferItemMessage> {
+    let mut transfers = vec![];
+    for (_, (pos, hopper)) in ecs.query::<(&BlockPos, &mut Hopper)>().iter() {
+        if !is_active(active_chunks, pos) {
+            continue;
+        }
+
+        hopper.timer = (hopper.timer + duration.as_secs_f32()).min(hopper.interval);
+        if hopper.timer < hopper.interval {
+            continue;
+        }
+
+        // Only move between blocks which hold items
+        let (Some(source), Some(dest)) = (
+            block_states.get(&(pos + hopper.from.offset())),
+            block_states.get(&(pos + hopper.to.offset())),
+        ) else {
+            continue;
         };
 
-        // Only process when we've got enough materials
-        let have_materials = recipe
-            .inputs
-            .iter()
-            .all(|(item, amount)| container.items[*item] >= *amount);
-        if !have_materials {
-            return;
+        // Items go through the faces touching the hopper
+        let source_side = Some(hopper.from.opposite());
+        let dest_side = Some(hopper.to.opposite());
+
+        // Find the first item which can make the trip
+        let transfer = (0..ItemType::LENGTH)
+            .map(ItemType::from_usize)
+            .filter(|item| hopper.allows(*item))
+            .find_map(|item| {
+                let available = with_container(ecs, *source, |c| {
+                    c.extract(item, hopper.stack_size, source_side, true)
+                })?
+                .iter()
+                .map(|stack| stack.count())
+                .sum::<usize>();
+                let space = with_container(ecs, *dest, |c| c.space_for_side(item, dest_side))?;
+
+                let count = available.min(space);
+                (count > 0).then_some((item, count))
+            });
+        let Some((item, count)) = transfer else {
+            continue;
+        };
+
+        hopper.timer = 0.;
+        transfers.push(TransferItemMessage {
+            source: *source,
+            dest: *dest,
+            item,
+            count,
+            source_side,
+            dest_side,
+        });
+    }
+
+    transfers
+}
+
+/// Work out what sorting chests pass on to the block next to them, anything their rules don't allow.
+/// Items are only passed on once per interval, and never into a loop of chests which would pass them
+/// straight back.
+pub fn sorting_tick(
+    ecs: &World,
+    block_states: &BlockStates,
+    active_chunks: &FxHashSet<ChunkPos>,
+    duration: &Duration,
+) -> Vec<TransferItemMessage> {
+    // Found up front, the block being sorted into might be another chest
+    let sorting = ecs
+        .query::<(&BlockPos, &Inventory, &mut ItemFilter)>()
+        .iter()
+        .filter(|(_, (pos, _, _))| is_active(active_chunks, pos))
+        .filter_map(|(entity, (pos, inventory, filter))| {
+            let side = filter.sort_to?;
+            filter.sort_timer =
+                (filter.sort_timer + duration.as_secs_f32()).min(ItemFilter::SORT_INTERVAL);
+            if filter.sort_timer < ItemFilter::SORT_INTERVAL {
+                return None;
+            }
+
+            let dest = block_states.get(&(pos + side.offset()))?;
+            let unmatched = inventory
+                .items
+                .iter()
+                .filter(|(item, count)| **count > 0 && !filter.matches(*item))
+                .map(|(item, count)| (item, *count))
+                .collect::<Vec<_>>();
+            Some((entity, *dest, side, unmatched))
+        })
+        .collect::<Vec<_>>();
+
+    let mut transfers = vec![];
+    for (source, dest, side, unmatched) in sorting {
+        let dest_side = Some(side.opposite());
+        for (item, held) in unmatched {
+            if !comes_to_rest(ecs, block_states, source, dest, item) {
+                continue;
+            }
+            let space =
+                with_container(ecs, dest, |c| c.space_for_side(item, dest_side)).unwrap_or(0);
+            if space == 0 {
+                continue;
+            }
+
+            transfers.push(TransferItemMessage {
+                source,
+                dest,
+                item,
+                count: held.min(space),
+                source_side: Some(side),
+                dest_side,
+            });
+        }
+
+        if let Ok(mut filter) = ecs.get::<&mut ItemFilter>(source) {
+            filter.sort_timer = 0.;
         }
+    }
 
-        // Make some progress on the recipe
-        crafter.crafting_juice += crafter.juice_per_second * duration.as_secs_f32();
+    transfers
+}
 
-        // Craft an item if we've got enough
-        if crafter.crafting_juice >= recipe.crafting_juice_cost {
-            crafter.crafting_juice -= recipe.crafting_juice_cost;
+/// Whether an item sent from one block into another stays somewhere along the line of sorting
+/// chests it's passed through, rather than going round in a loop back to where it started
+fn comes_to_rest(
+    ecs: &World,
+    block_states: &BlockStates,
+    source: Entity,
+    dest: Entity,
+    item: ItemType,
+) -> bool {
+    let mut visited = FxHashSet::from_iter([source]);
+    let mut current = dest;
+    loop {
+        if !visited.insert(current) {
+            return false;
+        }
 
-            recipe.inputs.iter().for_each(|(&item, &amount)| {
-                container.remove_item(item, amount);
+        // Anything but a sorting chest which doesn't want the item keeps it
+        let Ok(filter) = ecs.get::<&ItemFilter>(current) else {
+            return true;
+        };
+        let Some(side) = filter.sort_to.filter(|_| !filter.matches(item)) else {
+            return true;
+        };
+        let next = ecs
+            .get::<&BlockPos>(current)
+            .ok()
+            .and_then(|pos| block_states.get(&(&*pos + side.offset())).copied());
+        let Some(next) = next else {
+            return true;
+        };
+        current = next;
+    }
+}
+
+/// Carry items along conveyor belts, feeding them into whatever's at the end of the line
+pub fn conveyor_tick(
+    ecs: &mut World,
+    block_states: &BlockStates,
+    active_chunks: &FxHashSet<ChunkPos>,
+    duration: &Duration,
+) {
+    let step = Conveyor::SPEED * duration.as_secs_f32();
+    // Furthest an item's centre goes from the middle of its belt
+    let edge = 0.5 - Conveyor::ITEM_SIZE / 2.;
+
+    // Belts are found by position, so lines carry on across chunks
+    let belts = ecs
+        .query::<(&BlockPos, &Conveyor)>()
+        .iter()
+        .map(|(_, (pos, conveyor))| (pos.clone(), conveyor.direction))
+        .collect::<FxHashMap<_, _>>();
+
+    let mut items = ecs
+        .query::<(&Position, &ItemEntity)>()
+        .iter()
+        .map(|(entity, (pos, _))| (entity, pos.0))
+        .collect::<Vec<_>>();
+    let mut riding = group_by_belt(&items);
+
+    let mut delivered = FxHashSet::default();
+    for i in 0..items.len() {
+        let (entity, pos) = items[i];
+        let belt = Conveyor::under(&pos);
+        let Some(direction) = belts.get(&belt).filter(|_| is_active(active_chunks, &belt)) else {
+            continue;
+        };
+        let dir = direction.offset().cast::<f32>().unwrap();
+        let next = &belt + direction.offset();
+
+        let offset = pos.0 - Conveyor::item_centre(&belt).0;
+        let along = offset.dot(dir);
+
+        // End of the line, hand the item over if there's room
+        if !belts.contains_key(&next) && along >= edge - 1e-4 {
+            if let Some(dest) = block_states.get(&next) {
+                let stack = ecs.get::<&ItemEntity>(entity).unwrap().0.clone();
+                let side = Some(direction.opposite());
+
+                let fits = with_container(ecs, *dest, |c| c.insert(stack.clone(), side, true))
+                    .is_some_and(|leftover| leftover.is_none());
+                if fits {
+                    with_container(ecs, *dest, |c| c.insert(stack, side, false));
+                    wake(ecs, *dest);
+                    delivered.insert(i);
+                }
+            }
+            continue;
+        }
+
+        // Slide along, drifting back into the middle after going round corners
+        let across = offset - dir * along;
+        let across = if across.magnitude() > step {
+            across - across.normalize() * step
+        } else {
+            Vector3::zero()
+        };
+        let mut along = along + step;
+        if !belts.contains_key(&next) {
+            along = along.min(edge);
+        }
+        let new_pos = Conveyor::item_centre(&belt).0 + dir * along + across;
+
+        // Wait for the item in front to move on
+        let blocked = [&belt, &next]
+            .into_iter()
+            .filter_map(|b| riding.get(b))
+            .flatten()
+            .filter(|j| **j != i && !delivered.contains(*j))
+            .any(|j| {
+                let other = items[*j].1.0;
+                other.distance(new_pos) < Conveyor::ITEM_SPACING && (other - pos.0).dot(dir) > 0.
             });
+        if !blocked {
+            items[i].1 = WorldPos(new_pos);
+        }
+    }
 
-            container.add_item(recipe.output.0, recipe.output.1);
+    for (i, (entity, pos)) in items.iter().enumerate() {
+        if delivered.contains(&i) {
+            ecs.despawn(*entity).expect("Failed to destroy entity");
+        } else if let Ok(mut position) = ecs.get::<&mut Position>(*entity) {
+            position.0 = *pos;
         }
     }
+    let items = items
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| !delivered.contains(i))
+        .map(|(_, item)| item)
+        .collect::<Vec<_>>();
+    riding = group_by_belt(&items);
+
+    // Put waiting items on the start of their belt once there's room
+    let mut spawned = vec![];
+    for (_, (pos, conveyor)) in ecs.query_mut::<(&BlockPos, &mut Conveyor)>() {
+        if !is_active(active_chunks, pos) {
+            continue;
+        }
+
+        let Some(item) = (0..ItemType::LENGTH)
+            .map(ItemType::from_usize)
+            .find(|item| conveyor.input.items[*item] > 0)
+        else {
+            continue;
+        };
+
+        let dir = conveyor.direction.offset().cast::<f32>().unwrap();
+        let start = Conveyor::item_centre(pos) - dir * edge;
+        let blocked = riding
+            .get(pos)
+            .into_iter()
+            .flatten()
+            .any(|j| items[*j].1.0.distance(start.0) < Conveyor::ITEM_SPACING);
+        if blocked {
+            continue;
+        }
+
+        for stack in conveyor.input.take_items(item, 1) {
+            spawned.push((Position(start), ItemEntity(stack)));
+        }
+    }
+    ecs.spawn_batch(spawned);
+}
+
+/// Indices of items, grouped by the block they're riding on
+fn group_by_belt(items: &[(Entity, WorldPos)]) -> FxHashMap<BlockPos, Vec<usize>> {
+    let mut riding: FxHashMap<BlockPos, Vec<usize>> = FxHashMap::default();
+    for (i, (_, pos)) in items.iter().enumerate() {
+        riding.entry(Conveyor::under(pos)).or_default().push(i);
+    }
+
+    riding
+}
+
+/// Clear off a belt which is being broken, despawning the items riding it. Those & anything waiting
+/// to go on are given to `receiver`, then to any containers next to the belt.
+/// Returns whatever none of them had room for.
+pub fn empty_conveyor(
+    ecs: &mut World,
+    block_states: &BlockStates,
+    belt: Entity,
+    receiver: Entity,
+) -> Vec<ItemStack> {
+    let Ok(pos) = ecs.get::<&BlockPos>(belt).map(|pos| (*pos).clone()) else {
+        return vec![];
+    };
+
+    let riding = ecs
+        .query::<(&Position, &ItemEntity)>()
+        .iter()
+        .filter(|(_, (item_pos, _))| Conveyor::under(&item_pos.0) == pos)
+        .map(|(entity, (_, item))| (entity, item.0.clone()))
+        .collect::<Vec<_>>();
+    let mut stacks = vec![];
+    for (entity, stack) in riding {
+        ecs.despawn(entity).expect("Failed to destroy entity");
+        stacks.push(stack);
+    }
+    if let Ok(mut conveyor) = ecs.get::<&mut Conveyor>(belt) {
+        let waiting = conveyor.input.items;
+        for (item, count) in waiting.into_iter().filter(|(_, count)| *count > 0) {
+            stacks.extend(conveyor.input.take_items(item, count));
+        }
+    }
+
+    let neighbours = Side::ALL.iter().filter_map(|side| {
+        block_states
+            .get(&(&pos + side.offset()))
+            .map(|e| (*e, side.opposite()))
+    });
+    let receivers = std::iter::once((receiver, None))
+        .chain(neighbours.map(|(entity, side)| (entity, Some(side))))
+        .filter(|(entity, _)| *entity != belt)
+        .collect::<Vec<_>>();
+
+    stacks
+        .into_iter()
+        .filter_map(|stack| {
+            receivers.iter().try_fold(stack, |stack, (entity, side)| {
+                with_container(ecs, *entity, |c| c.insert(stack.clone(), *side, false))
+                    .unwrap_or(Some(stack))
+            })
+        })
+        .collect()
+}
+
+/// Discover any new items entities have picked up
+pub fn progression_tick(ecs: &mut World) {
+    for (_, (container, progression)) in ecs.query_mut::<(&Inventory, &mut Progression)>() {
+        progression.discover(container);
+    }
 }
 
 /// Creates a block with the default state
@@ -85,41 +488,197 @@ pub fn create_block_state(ecs: &mut World, pos: &BlockPos, block_type: BlockType
     ecs.spawn(entity.build())
 }
 
-/// Transfer items between two sources
-pub fn transfer_item(ecs: &mut World, message: &TransferItemMessage) {
+/// Creates an entity from its user-defined data, facing the way entities start out
+pub fn spawn_entity(ecs: &mut World, pos: WorldPos, entity_type: EntityType) -> Entity {
+    let data = entity_type.data();
+
+    let mut entity = EntityBuilder::new();
+    entity.add_bundle((
+        Position(pos),
+        Orientation(Quaternion::from_angle_y(Deg(180.))),
+        entity_type,
+        data.hitbox.clone(),
+        Health::full(data.health),
+        Movement {
+            speed: data.speed,
+            vertical_velocity: 0.,
+        },
+        Vision(data.vision),
+        data.behaviour.clone(),
+        Brain::default(),
+    ));
+    if let Some(state_fn) = data.state {
+        state_fn(&mut entity);
+    }
+
+    ecs.spawn(entity.build())
+}
+
+/// Move up to `count` items between two containers, as many as are available & will fit.
+/// Returns the number moved, nothing changes if none could be.
+pub fn transfer_item(
+    ecs: &mut World,
+    message: &TransferItemMessage,
+) -> Result<usize, TransferError> {
     let TransferItemMessage {
         source,
         dest,
         item,
         count,
+        source_side,
+        dest_side,
     } = *message;
 
-    ecs.get::<&mut Container>(source)
-        .expect("Failed to get source entity for item transfer")
-        .remove_item(item, count);
+    // Check it can go through before touching anything
+    let space = with_container(ecs, dest, |dest| dest.space_for_side(item, dest_side))
+        .ok_or(TransferError::NoContainer)?;
+    let available = with_container(ecs, source, |source| {
+        source.extract(item, count, source_side, true)
+    })
+    .ok_or(TransferError::NoContainer)?
+    .iter()
+    .map(|stack| stack.count())
+    .sum::<usize>();
+
+    if available == 0 {
+        return Err(TransferError::NothingToMove);
+    }
+    if space == 0 {
+        return Err(TransferError::NoSpace);
+    }
+
+    // Settled before taking anything, so refused items go back where they came from
+    let source_side = with_container(ecs, source, |source| source.output_side(item, source_side))
+        .ok_or(TransferError::NoContainer)?;
+    let stacks = with_container(ecs, source, |source| {
+        source.extract(item, available.min(space), source_side, false)
+    })
+    .ok_or(TransferError::NoContainer)?;
+    let taken = stacks.iter().map(|stack| stack.count()).sum::<usize>();
+
+    let leftovers = with_container(ecs, dest, |dest| {
+        stacks
+            .into_iter()
+            .filter_map(|stack| dest.insert(stack, dest_side, false))
+            .collect::<Vec<_>>()
+    })
+    .ok_or(TransferError::NoContainer)?;
+
+    // Anything refused goes back where it came from
+    let refused = leftovers.iter().map(|stack| stack.count()).sum::<usize>();
+    if refused > 0 {
+        log::warn!("{refused} {item:?} didn't fit after all, putting them back");
+        put_back(ecs, source, source_side, leftovers);
+    }
+
+    match taken - refused {
+        0 => Err(TransferError::NoSpace),
+        moved => {
+            // Either end might have been waiting on these
+            wake(ecs, source);
+            wake(ecs, dest);
+            Ok(moved)
+        }
+    }
+}
+
+/// Return items to the inventory they were taken out of, eg. when the destination refused them.
+/// They skip the usual checks, so a filter changed since they went in can't destroy them.
+/// The side should be the one they really came out of, see [`Container::output_side`].
+fn put_back(ecs: &World, source: Entity, side: Option<Side>, stacks: Vec<ItemStack>) {
+    let put_back = with_container(ecs, source, |source| {
+        source
+            .output(side)
+            .map(|inventory| {
+                stacks
+                    .iter()
+                    .for_each(|stack| inventory.insert_stack(stack.clone()))
+            })
+            .is_some()
+    });
+    if put_back != Some(true) {
+        log::warn!("Nowhere to put back {stacks:?}, lost!");
+    }
+}
 
-    ecs.get::<&mut Container>(dest)
-        .expect("Failed to get dest entity for item transfer")
-        .add_item(item, count);
+/// Whether block entities at a position are running
+fn is_active(active_chunks: &FxHashSet<ChunkPos>, pos: &BlockPos) -> bool {
+    active_chunks.contains(&pos.to_chunk_offset().0)
 }
 
-/// Get the block breaking strength for an entity
-pub fn get_breaking_strength(ecs: &World, entity: Entity) -> u32 {
-    if let Some((item, count)) = get_held_item(ecs, entity)
-        && count > 0
-    {
-        ITEMS.get().unwrap()[item]
-            .data
-            .breaking_strength
-            .unwrap_or(0)
+/// Run a function on whichever container an entity has. None if it doesn't hold items.
+pub fn with_container<R>(
+    ecs: &World,
+    entity: Entity,
+    f: impl FnOnce(&mut dyn Container) -> R,
+) -> Option<R> {
+    if let Ok(mut inventory) = ecs.get::<&mut Inventory>(entity) {
+        // Filtered chests only take what their rules allow
+        if let Ok(filter) = ecs.get::<&ItemFilter>(entity) {
+            return Some(f(&mut Filtered {
+                inventory: &mut inventory,
+                filter: &filter,
+            }));
+        }
+        return Some(f(&mut *inventory));
+    }
+    if let Ok(mut crafter) = ecs.get::<&mut Crafter>(entity) {
+        return Some(f(&mut *crafter));
+    }
+    if let Ok(mut conveyor) = ecs.get::<&mut Conveyor>(entity) {
+        return Some(f(&mut *conveyor));
+    }
+    if let Ok(mut generator) = ecs.get::<&mut Generator>(entity) {
+        return Some(f(&mut *generator));
+    }
+    if let Ok(mut furnace) = ecs.get::<&mut Furnace>(entity) {
+        return Some(f(&mut *furnace));
+    }
+
+    None
+}
+
+/// Get what an entity is able to mine with the item it's holding
+pub fn get_mining_capability(ecs: &World, entity: Entity) -> MiningCapability {
+    let Some((item, count)) = get_held_item(ecs, entity) else {
+        return MiningCapability::default();
+    };
+    if count == 0 {
+        return MiningCapability::default();
+    }
+
+    let item_data = &ITEMS.get().unwrap()[item].data;
+
+    // Unique items may have modifiers changing their strength
+    let inventory = ecs.get::<&Inventory>(entity).unwrap();
+    let breaking_strength = if let Some(instance) = inventory.active_instance(item) {
+        instance.breaking_strength()
     } else {
-        0
+        item_data.breaking_strength
     }
+    .unwrap_or(0);
+
+    MiningCapability {
+        breaking_strength,
+        tool: item_data.tool,
+    }
+}
+
+/// Wear down the item an entity is holding. Returns true if the item broke.
+pub fn wear_held_item(ecs: &mut World, entity: Entity, amount: u32) -> bool {
+    let mut query = ecs.query_one::<(&mut Inventory, &Hotbar)>(entity).unwrap();
+    let (inventory, hotbar) = query.get().unwrap();
+
+    let Some(item) = hotbar.slots[hotbar.selected] else {
+        return false;
+    };
+
+    inventory.wear_item(item, amount)
 }
 
 /// Get the item & count an entity is holding
 pub fn get_held_item(ecs: &World, entity: Entity) -> Option<(ItemType, usize)> {
-    let mut query = ecs.query_one::<(&Container, &Hotbar)>(entity).unwrap();
+    let mut query = ecs.query_one::<(&Inventory, &Hotbar)>(entity).unwrap();
     let (inventory, hotbar) = query.get().unwrap();
 
     hotbar.slots[hotbar.selected].map(|item| (item, inventory.items[item]))
@@ -127,7 +686,11 @@ pub fn get_held_item(ecs: &World, entity: Entity) -> Option<(ItemType, usize)> {
 
 /// Spawn the default player
 pub fn spawn_player(ecs: &mut World) -> Entity {
-    let mut inventory = Container::default();
+    let mut inventory = Inventory::with_capacity(Capacity {
+        max_weight: Some(200.),
+        max_slots: Some(36),
+        max_stack: Some(64),
+    });
     inventory.add_item(ItemType::Dirt, 5);
     inventory.add_item(ItemType::Stone, 12);
     inventory.add_item(ItemType::Coal, 12);
@@ -168,5 +731,431 @@ pub fn spawn_player(ecs: &mut World) -> Entity {
         Reach(5.),
         aabb,
         InteractionMode::Game,
+        Progression::default(),
+        Mining::default(),
+        ItemViews::default(),
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use cgmath::Point3;
+    use hecs::World;
+    use rustc_hash::FxHashSet;
+
+    use crate::{
+        container::{Side, TransferError},
+        data::{
+            entity::EntityType,
+            item::{ItemCategory, ItemType},
+        },
+        entity::{
+            bundles::BlockStates,
+            components::{
+                Capacity, Conveyor, FilterMode, FilterRule, Furnace, Health, Hopper, Inventory,
+                ItemEntity, ItemFilter, Movement, Position,
+            },
+            systems::{
+                conveyor_tick, empty_conveyor, hopper_tick, put_back, spawn_entity, transfer_item,
+                with_container,
+            },
+        },
+        event::messages::TransferItemMessage,
+        item::ItemStack,
+        math::bbox::AABB,
+        state::world::{BlockPos, ChunkPos, WorldPos},
+    };
+
+    #[test]
+    fn test_conveyor_line() {
+        let mut ecs = World::new();
+        let mut block_states = BlockStates::default();
+
+        // Two belts heading east into a chest with room for one item
+        for x in 0..2 {
+            let pos = BlockPos::new(x, 0, 0);
+            let mut conveyor = Conveyor {
+                direction: Side::East,
+                ..Default::default()
+            };
+            if x == 0 {
+                conveyor.input.add_item(ItemType::Stone, 2);
+            }
+            block_states.insert(pos.clone(), ecs.spawn((pos, conveyor)));
+        }
+        let chest_pos = BlockPos::new(2, 0, 0);
+        let chest = Inventory::with_capacity(Capacity {
+            max_slots: Some(1),
+            max_stack: Some(1),
+            ..Default::default()
+        });
+        let chest = ecs.spawn((chest_pos.clone(), chest));
+        block_states.insert(chest_pos, chest);
+
+        for _ in 0..100 {
+            conveyor_tick(
+                &mut ecs,
+                &block_states,
+                &FxHashSet::from_iter([ChunkPos::new(0, 0, 0)]),
+                &Duration::from_millis(50),
+            );
+        }
+
+        // The first stone made it, the second is stuck at the end of the line
+        assert_eq!(
+            ecs.get::<&Inventory>(chest).unwrap().items[ItemType::Stone],
+            1
+        );
+        let mut riding = ecs.query::<(&Position, &ItemEntity)>();
+        let riding = riding.iter().map(|(_, (pos, _))| pos.0).collect::<Vec<_>>();
+        assert_eq!(riding.len(), 1);
+        assert_eq!(Conveyor::under(&riding[0]), BlockPos::new(1, 0, 0));
+        assert!(riding[0].0.x > 1.8);
+    }
+
+    #[test]
+    fn test_hopper() {
+        let mut ecs = World::new();
+        let mut block_states = BlockStates::default();
+
+        // Hopper only letting copper down from one chest into another with room for two
+        let hopper = Hopper {
+            filter: vec![ItemType::Copper],
+            ..Default::default()
+        };
+        ecs.spawn((BlockPos::new(0, 0, 0), hopper));
+        let mut source = Inventory::default();
+        source.add_item(ItemType::Stone, 3);
+        source.add_item(ItemType::Copper, 3);
+        let source_pos = BlockPos::new(0, 1, 0);
+        let source = ecs.spawn((source_pos.clone(), source));
+        block_states.insert(source_pos, source);
+        let dest = Inventory::with_capacity(Capacity {
+            max_slots: Some(1),
+            max_stack: Some(2),
+            ..Default::default()
+        });
+        let dest_pos = BlockPos::new(0, -1, 0);
+        let dest = ecs.spawn((dest_pos.clone(), dest));
+        block_states.insert(dest_pos, dest);
+
+        // Carry out whatever the hopper asks for, like the game would
+        let tick = |ecs: &mut World| {
+            let transfers = hopper_tick(
+                ecs,
+                &block_states,
+                &FxHashSet::from_iter([ChunkPos::new(0, 0, 0)]),
+                &Duration::from_millis(200),
+            );
+            transfers.iter().for_each(|m| {
+                transfer_item(ecs, m).unwrap();
+            });
+            transfers
+        };
+        let held = |ecs: &World, entity, item| ecs.get::<&Inventory>(entity).unwrap().items[item];
+
+        // Nothing moves until the interval is up
+        assert!(tick(&mut ecs).is_empty());
+        assert!(tick(&mut ecs).is_empty());
+        assert!(matches!(
+            tick(&mut ecs).as_slice(),
+            [TransferItemMessage {
+                item: ItemType::Copper,
+                count: 1,
+                source_side: Some(Side::Down),
+                dest_side: Some(Side::Up),
+                ..
+            }]
+        ));
+        assert_eq!(held(&ecs, dest, ItemType::Copper), 1);
+
+        // Stops once the destination is full, never touching the stone
+        for _ in 0..20 {
+            tick(&mut ecs);
+        }
+        assert!(tick(&mut ecs).is_empty());
+        assert_eq!(held(&ecs, dest, ItemType::Copper), 2);
+        assert_eq!(held(&ecs, dest, ItemType::Stone), 0);
+        assert_eq!(held(&ecs, source, ItemType::Copper), 1);
+        assert_eq!(held(&ecs, source, ItemType::Stone), 3);
+    }
+
+    #[test]
+    fn test_empty_conveyor() {
+        let mut ecs = World::new();
+        let mut block_states = BlockStates::default();
+
+        // Belt with copper waiting & stone riding it, next to a chest with another belt nearby
+        let pos = BlockPos::new(0, 0, 0);
+        let mut conveyor = Conveyor::default();
+        conveyor.input.add_item(ItemType::Copper, 2);
+        let belt = ecs.spawn((pos.clone(), conveyor));
+        let next_pos = BlockPos::new(2, 0, 0);
+        block_states.insert(
+            next_pos.clone(),
+            ecs.spawn((next_pos.clone(), Conveyor::default())),
+        );
+        let chest_pos = BlockPos::new(-1, 0, 0);
+        let chest = ecs.spawn((chest_pos.clone(), Inventory::default()));
+        block_states.insert(chest_pos, chest);
+        let stone = |pos| {
+            (
+                Position(Conveyor::item_centre(pos)),
+                ItemEntity(ItemStack::Counted(ItemType::Stone, 1)),
+            )
+        };
+        ecs.spawn(stone(&pos));
+        ecs.spawn(stone(&next_pos));
+
+        // Player only has room for the stone, so the copper goes in the chest
+        let player = ecs.spawn((Inventory::with_capacity(Capacity {
+            max_slots: Some(1),
+            max_stack: Some(1),
+            ..Default::default()
+        }),));
+        let lost = empty_conveyor(&mut ecs, &block_states, belt, player);
+        assert!(lost.is_empty());
+        assert_eq!(
+            ecs.get::<&Inventory>(player).unwrap().items[ItemType::Stone],
+            1
+        );
+        assert_eq!(
+            ecs.get::<&Inventory>(chest).unwrap().items[ItemType::Copper],
+            2
+        );
+        assert_eq!(
+            ecs.get::<&Conveyor>(belt).unwrap().input.items[ItemType::Copper],
+            0
+        );
+
+        // Only the item on the broken belt is gone
+        let mut riding = ecs.query::<(&Position, &ItemEntity)>();
+        let riding = riding.iter().map(|(_, (pos, _))| pos.0).collect::<Vec<_>>();
+        assert_eq!(riding.len(), 1);
+        assert_eq!(Conveyor::under(&riding[0]), next_pos);
+    }
+
+    #[test]
+    fn test_transfer_item() {
+        let mut ecs = World::new();
+        let mut source = Inventory::default();
+        source.add_item(ItemType::Copper, 5);
+        let source = ecs.spawn((source,));
+        let dest = ecs.spawn((Inventory::with_capacity(Capacity {
+            max_slots: Some(1),
+            max_stack: Some(3),
+            ..Default::default()
+        }),));
+
+        let message = TransferItemMessage {
+            source,
+            dest,
+            item: ItemType::Copper,
+            count: 5,
+            source_side: None,
+            dest_side: None,
+        };
+
+        // Only what fits is moved, the rest stays put
+        assert_eq!(transfer_item(&mut ecs, &message), Ok(3));
+        assert_eq!(
+            ecs.get::<&Inventory>(source).unwrap().items[ItemType::Copper],
+            2
+        );
+        assert_eq!(
+            ecs.get::<&Inventory>(dest).unwrap().items[ItemType::Copper],
+            3
+        );
+
+        // Nothing changes once the request is stale
+        assert_eq!(
+            transfer_item(&mut ecs, &message),
+            Err(TransferError::NoSpace)
+        );
+        let empty = TransferItemMessage {
+            item: ItemType::Tin,
+            ..message
+        };
+        assert_eq!(
+            transfer_item(&mut ecs, &empty),
+            Err(TransferError::NothingToMove)
+        );
+        ecs.despawn(dest).unwrap();
+        assert_eq!(
+            transfer_item(&mut ecs, &message),
+            Err(TransferError::NoContainer)
+        );
+        assert_eq!(
+            ecs.get::<&Inventory>(source).unwrap().items[ItemType::Copper],
+            2
+        );
+    }
+
+    #[test]
+    fn test_filtered_chest() {
+        let mut ecs = World::new();
+        let mut player = Inventory::default();
+        player.add_item(ItemType::Copper, 5);
+        player.add_item(ItemType::CopperPickaxe, 1);
+        let player = ecs.spawn((player,));
+        let filter = ItemFilter {
+            mode: FilterMode::Blacklist,
+            rules: vec![FilterRule::Category(ItemCategory::Tools)],
+            ..Default::default()
+        };
+        let chest = ecs.spawn((Inventory::default(), filter));
+
+        let message = TransferItemMessage {
+            source: player,
+            dest: chest,
+            item: ItemType::Copper,
+            count: 5,
+            source_side: None,
+            dest_side: None,
+        };
+        let tool = TransferItemMessage {
+            item: ItemType::CopperPickaxe,
+            count: 1,
+            ..message
+        };
+
+        // The player can't put in what the rules don't allow either
+        assert_eq!(transfer_item(&mut ecs, &message), Ok(5));
+        assert_eq!(transfer_item(&mut ecs, &tool), Err(TransferError::NoSpace));
+
+        // Sorting chests take it to pass on
+        ecs.get::<&mut ItemFilter>(chest).unwrap().sort_to = Some(Side::East);
+        assert_eq!(transfer_item(&mut ecs, &tool), Ok(1));
+    }
+
+    #[test]
+    fn test_sorting_chests() {
+        let mut ecs = World::new();
+        let mut block_states = BlockStates::default();
+
+        // Two sorting chests facing each other, one keeping copper and the other tin
+        let sorting = |item, sort_to| ItemFilter {
+            rules: vec![FilterRule::Item(item)],
+            sort_to: Some(sort_to),
+            ..Default::default()
+        };
+        let mut west = Inventory::default();
+        west.add_item(ItemType::Tin, 3);
+        west.add_item(ItemType::Stone, 2);
+        let west_pos = BlockPos::new(0, 0, 0);
+        let west = ecs.spawn((
+            west_pos.clone(),
+            west,
+            sorting(ItemType::Copper, Side::East),
+        ));
+        block_states.insert(west_pos, west);
+        let east_pos = BlockPos::new(1, 0, 0);
+        let east = ecs.spawn((
+            east_pos.clone(),
+            Inventory::default(),
+            sorting(ItemType::Tin, Side::West),
+        ));
+        block_states.insert(east_pos, east);
+
+        let tick = |ecs: &mut World| {
+            let transfers = sorting_tick(
+                ecs,
+                &block_states,
+                &FxHashSet::from_iter([ChunkPos::new(0, 0, 0)]),
+                &Duration::from_millis(300),
+            );
+            transfers.iter().for_each(|m| {
+                transfer_item(ecs, m).unwrap();
+            });
+            transfers
+        };
+        let held = |ecs: &World, entity, item| ecs.get::<&Inventory>(entity).unwrap().items[item];
+
+        // The tin goes across once the interval is up, the stone neither wants stays put
+        assert!(tick(&mut ecs).is_empty());
+        assert!(matches!(
+            tick(&mut ecs).as_slice(),
+            [TransferItemMessage {
+                item: ItemType::Tin,
+                count: 3,
+                source_side: Some(Side::East),
+                dest_side: Some(Side::West),
+                ..
+            }]
+        ));
+        assert_eq!(held(&ecs, east, ItemType::Tin), 3);
+
+        // Rather than going back and forth forever
+        for _ in 0..10 {
+            assert!(tick(&mut ecs).is_empty());
+        }
+        assert_eq!(held(&ecs, west, ItemType::Stone), 2);
+        assert_eq!(held(&ecs, east, ItemType::Tin), 3);
+    }
+
+    #[test]
+    fn test_put_back() {
+        let mut ecs = World::new();
+
+        // Chest holding a pickaxe from before tools were blacklisted
+        let mut chest = Inventory::default();
+        chest.add_item(ItemType::CopperPickaxe, 1);
+        let filter = ItemFilter {
+            mode: FilterMode::Blacklist,
+            rules: vec![FilterRule::Category(ItemCategory::Tools)],
+            ..Default::default()
+        };
+        let chest = ecs.spawn((chest, filter));
+
+        // Refused on the way out, it still goes back in rather than being lost
+        let stacks = with_container(&ecs, chest, |c| {
+            c.extract(ItemType::CopperPickaxe, 1, None, false)
+        })
+        .unwrap();
+        assert_eq!(
+            ecs.get::<&Inventory>(chest).unwrap().items[ItemType::CopperPickaxe],
+            0
+        );
+        put_back(&ecs, chest, None, stacks);
+        {
+            let chest = ecs.get::<&Inventory>(chest).unwrap();
+            assert_eq!(chest.items[ItemType::CopperPickaxe], 1);
+            assert_eq!(chest.instances.len(), 1);
+        }
+
+        // Fuel taken straight out of a furnace goes back in as fuel, not as a product
+        let mut furnace = Furnace::default();
+        furnace.burner.fuel.add_item(ItemType::Coal, 2);
+        let furnace = ecs.spawn((furnace,));
+        let (side, stacks) = with_container(&ecs, furnace, |c| {
+            let side = c.output_side(ItemType::Coal, None);
+            (side, c.extract(ItemType::Coal, 2, side, false))
+        })
+        .unwrap();
+        put_back(&ecs, furnace, side, stacks);
+        let furnace = ecs.get::<&Furnace>(furnace).unwrap();
+        assert_eq!(furnace.burner.fuel.items[ItemType::Coal], 2);
+        assert_eq!(furnace.output.items[ItemType::Coal], 0);
+    }
+
+    #[test]
+    fn test_spawn_entity() {
+        let mut ecs = World::new();
+        let pos = WorldPos(Point3::new(1., 2., 3.));
+        let entity = spawn_entity(&mut ecs, pos, EntityType::Sibeal);
+
+        // Everything comes from the entity's data
+        let data = EntityType::Sibeal.data();
+        let mut query = ecs
+            .query_one::<(&Position, &EntityType, &Health, &Movement, &AABB<f32>)>(entity)
+            .unwrap();
+        let (position, entity_type, health, movement, hitbox) = query.get().unwrap();
+        assert_eq!(position.0.0, pos.0);
+        assert_eq!(*entity_type, EntityType::Sibeal);
+        assert_eq!(health.current, data.health);
+        assert_eq!(movement.speed, data.speed);
+        assert_eq!(hitbox.end, data.hitbox.end);
+    }
+}
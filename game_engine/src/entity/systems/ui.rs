@@ -1,83 +1,203 @@
 use egui::{
-    Align2, Color32, Frame, Popup, Sense, Stroke, Vec2, Vec2b, Window,
-    scroll_area::ScrollBarVisibility,
+    Align2, Color32, ComboBox, DragValue, Frame, Modifiers, Response, Sense, Stroke, TextEdit, Ui,
+    Vec2, Vec2b, Window, scroll_area::ScrollBarVisibility,
 };
 use hecs::EntityRef;
 
 use crate::{
-    data::{loader::ITEMS, recipe::RECIPES},
-    entity::components::{Container, Crafter, Hotbar, UIType},
+    container::Side,
+    data::{
+        item::{ItemCategory, ItemType},
+        loader::ITEMS,
+        recipe::RECIPES,
+    },
+    entity::components::{
+        Burner, ChunkLoader, Conveyor, Crafter, CrafterUpdate, CraftingJob, FilterMode, FilterRule,
+        Furnace, Generator, Hopper, Hotbar, Inventory, ItemFilter, ItemSort, ItemView, ItemViews,
+        JobTarget, PowerNode, Progression, StorageTerminal, UIType,
+    },
     event::{
         MESSAGE_QUEUE, Message,
         messages::{
-            ItemFavouritedMessage, SetCraftingRecipeMessage, TransferItemRequestMessage,
-            TransferItemSource,
+            ItemFavouritedMessage, SetConveyorDirectionMessage, SetHopperConfigMessage,
+            SetItemFilterMessage, SetItemViewMessage, TransferItemRequestMessage,
+            TransferItemSource, UpdateCrafterMessage,
         },
     },
     state::world::BlockPos,
     ui::{
         Icon,
-        helpers::{draw_item_grid, draw_progress_bar, draw_recipe},
+        helpers::{
+            draw_capacity, draw_instance_tooltip, draw_item_grid, draw_network_status,
+            draw_progress_bar, draw_recipe,
+        },
     },
 };
 
-pub fn draw_ui(ctx: &egui::Context, entity: EntityRef<'_>) {
+/// Draw the interface for a block. The player is the one interacting with it.
+pub fn draw_ui(ctx: &egui::Context, entity: EntityRef<'_>, player: EntityRef<'_>) {
     let ui_type = entity
         .get::<&UIType>()
         .expect("Entity has no UI component!");
 
     match *ui_type {
-        UIType::Chest => draw_chest(ctx, entity),
-        UIType::Crafter => draw_crafter(ctx, entity),
+        UIType::Chest => draw_chest(ctx, entity, player),
+        UIType::Crafter => draw_crafter(ctx, entity, player),
+        UIType::Hopper => draw_hopper(ctx, entity),
+        UIType::Conveyor => draw_conveyor(ctx, entity),
+        UIType::Generator => draw_generator(ctx, entity),
+        UIType::Conduit => draw_conduit(ctx, entity),
+        UIType::Furnace => draw_furnace(ctx, entity),
+        UIType::ChunkLoader => draw_chunk_loader(ctx, entity),
+        UIType::StorageTerminal => draw_storage_terminal(ctx, entity, player),
     }
 }
 
-pub fn draw_chest(ctx: &egui::Context, entity: EntityRef<'_>) {
-    let mut entity = entity.query::<(&BlockPos, &Container)>();
-    let (block_pos, container) = entity
+pub fn draw_chest(ctx: &egui::Context, entity: EntityRef<'_>, player: EntityRef<'_>) {
+    let mut entity = entity.query::<(&BlockPos, &Inventory, &ItemFilter)>();
+    let (block_pos, container, filter) = entity
         .get()
         .expect("Chest doesn't have the right components!");
 
+    draw_item_window(ctx, "Chest", block_pos, container, player, |ui| {
+        draw_capacity(ui, container);
+        draw_item_filter(ui, block_pos, filter);
+    });
+}
+
+/// Window of the items held by a block, which can be searched, sorted & moved in & out.
+/// `header` draws anything specific to the block above the items.
+fn draw_item_window(
+    ctx: &egui::Context,
+    title: &str,
+    block_pos: &BlockPos,
+    container: &Inventory,
+    player: EntityRef<'_>,
+    header: impl FnOnce(&mut Ui),
+) {
     let icon_size = 32.;
     let num_slots = 8;
 
     let window_size = Vec2::new(icon_size, icon_size) * num_slots as f32;
 
-    Window::new("Chest")
+    Window::new(title)
         .resizable(false)
         // Scroll bar for when we have lots of items
         .scroll(Vec2b { x: false, y: true })
         .scroll_bar_visibility(ScrollBarVisibility::VisibleWhenNeeded)
         .default_width(window_size.x)
         .show(ctx, |ui| {
-            draw_item_grid(ui, "chest", &container.items, icon_size)
-                .into_iter()
-                // Filter out responses that weren't drawn
-                .filter_map(|(id, resp)| resp.map(|resp| (id, resp)))
-                .for_each(|(id, resp)| {
-                    // Detect keypresses
-                    if resp.hovered() {
-                        use egui::Key::*;
-                        // Item transfer
-                        if ui.input(|i| i.key_pressed(T)) {
-                            MESSAGE_QUEUE.send(Message::TransferItemRequest(
-                                TransferItemRequestMessage {
-                                    item: id,
-                                    count: 1,
-                                    source: TransferItemSource::Block(block_pos.clone()),
-                                },
-                            ));
-                        }
-                    }
+            let source = TransferItemSource::Block(block_pos.clone());
+
+            header(ui);
+            let typed = draw_transfer_controls(ui, container, &source);
+            let view = draw_item_view_controls(ui, title, player);
+            draw_drop_zone(ui, &source, |ui| {
+                draw_item_grid(ui, title, view.apply(&container.items), icon_size)
+                    .into_iter()
+                    // Filter out responses that weren't drawn
+                    .filter_map(|(id, resp)| resp.map(|resp| (id, resp)))
+                    .for_each(|(id, resp)| {
+                        draw_instance_tooltip(&resp, container, id);
+                        handle_item_transfer(ui, &resp, container, id, &source, None, typed);
+                    });
+            });
+        });
+}
+
+/// Rules on what a chest takes in, & where sorting chests send everything else
+fn draw_item_filter(ui: &mut Ui, block_pos: &BlockPos, filter: &ItemFilter) {
+    let items = ITEMS.get().expect("Item info not initialised!");
+
+    // Edit a copy, then send it off if anything changed
+    let mut edited = filter.clone();
+
+    ui.collapsing("Filter", |ui| {
+        ui.horizontal(|ui| {
+            ComboBox::from_id_salt("filter mode")
+                .selected_text(format!("{:?}", edited.mode))
+                .show_ui(ui, |ui| {
+                    [FilterMode::Whitelist, FilterMode::Blacklist]
+                        .into_iter()
+                        .for_each(|mode| {
+                            ui.selectable_value(&mut edited.mode, mode, format!("{mode:?}"));
+                        });
                 });
+            ui.label("Priority");
+            ui.add(DragValue::new(&mut edited.priority));
+        });
+
+        // Sorting chests pass unmatched items on instead of refusing them
+        ui.horizontal(|ui| {
+            let mut sorting = edited.sort_to.is_some();
+            ui.checkbox(&mut sorting, "Sort unmatched items to");
+            edited.sort_to = sorting.then(|| edited.sort_to.unwrap_or(Side::Down));
+            if let Some(side) = &mut edited.sort_to {
+                ComboBox::from_id_salt("sort to")
+                    .selected_text(format!("{side:?}"))
+                    .show_ui(ui, |ui| {
+                        Side::ALL.into_iter().for_each(|s| {
+                            ui.selectable_value(side, s, format!("{s:?}"));
+                        });
+                    });
+            }
         });
+
+        // Categories & items, nothing selected lets everything through
+        let mut toggle = |ui: &mut Ui, rule: FilterRule, name: &str| {
+            let selected = edited.rules.contains(&rule);
+            if ui.selectable_label(selected, name).clicked() {
+                if selected {
+                    edited.rules.retain(|r| *r != rule);
+                } else {
+                    edited.rules.push(rule);
+                }
+            }
+        };
+        ui.horizontal_wrapped(|ui| {
+            ItemCategory::ALL.into_iter().for_each(|category| {
+                toggle(ui, FilterRule::Category(category), category.name());
+            });
+        });
+        ui.horizontal_wrapped(|ui| {
+            items.iter().for_each(|(item, info)| {
+                toggle(ui, FilterRule::Item(item), info.data.name);
+            });
+        });
+    });
+
+    if edited != *filter {
+        MESSAGE_QUEUE.send(Message::SetItemFilter(SetItemFilterMessage {
+            block: block_pos.clone(),
+            filter: edited,
+        }));
+    }
 }
 
-pub fn draw_crafter(ctx: &egui::Context, entity: EntityRef<'_>) {
-    let mut entity = entity.query::<(&BlockPos, &Container, &Crafter)>();
-    let (block_pos, container, crafter) = entity
+/// Like a chest, but showing everything in the chests on its network
+pub fn draw_storage_terminal(ctx: &egui::Context, entity: EntityRef<'_>, player: EntityRef<'_>) {
+    let mut entity = entity.query::<(&BlockPos, &StorageTerminal)>();
+    let (block_pos, terminal) = entity
         .get()
-        .expect("Chest doesn't have the right components!");
+        .expect("Storage terminal doesn't have the right components!");
+
+    let contents = &terminal.contents;
+    draw_item_window(ctx, "Storage Terminal", block_pos, contents, player, |ui| {
+        match terminal.chests.len() {
+            0 => ui.colored_label(Color32::LIGHT_RED, "No chests connected"),
+            chests => ui.label(format!("Connected chests: {chests}")),
+        };
+    });
+}
+
+pub fn draw_crafter(ctx: &egui::Context, entity: EntityRef<'_>, player: EntityRef<'_>) {
+    let mut entity = entity.query::<(&BlockPos, &Crafter, &PowerNode)>();
+    let (block_pos, crafter, node) = entity
+        .get()
+        .expect("Crafter doesn't have the right components!");
+    let progression = player
+        .get::<&Progression>()
+        .expect("Player doesn't have progression!");
 
     let icon_size = 32.;
     let num_slots = 8;
@@ -85,74 +205,555 @@ pub fn draw_crafter(ctx: &egui::Context, entity: EntityRef<'_>) {
 
     let window_size = Vec2::new(icon_size, icon_size) * num_slots as f32;
 
-    Window::new("Crafter")
+    Window::new(crafter.category.machine_name())
         .resizable(false)
         // Scroll bar for when we have lots of items
         .scroll(Vec2b { x: false, y: true })
         .scroll_bar_visibility(ScrollBarVisibility::VisibleWhenNeeded)
         .default_width(window_size.x)
         .show(ctx, |ui| {
+            let update = |update| {
+                MESSAGE_QUEUE.send(Message::UpdateCrafter(UpdateCrafterMessage {
+                    block: block_pos.clone(),
+                    update,
+                }))
+            };
+
             // Recipe selector
             let recipe_menu = |ui: &mut egui::Ui| {
-                RECIPES.iter().for_each(|recipe| {
-                    if draw_recipe(ui, recipe, icon_size, font_size).clicked() {
-                        MESSAGE_QUEUE.send(Message::SetCraftingRecipe(SetCraftingRecipeMessage {
-                            block: block_pos.clone(),
-                            recipe: recipe.clone(),
-                        }));
-                    }
-                });
+                crafter
+                    .category
+                    .recipes()
+                    .iter()
+                    // Don't reveal recipes the player hasn't come across yet
+                    .filter(|recipe| progression.is_unlocked(recipe))
+                    .for_each(|recipe| {
+                        let can_automate = progression.can_automate(recipe);
+                        let resp = ui
+                            .add_enabled_ui(can_automate, |ui| {
+                                draw_recipe(ui, recipe, icon_size, font_size)
+                            })
+                            .inner;
+
+                        if can_automate {
+                            if resp.clicked() {
+                                update(CrafterUpdate::Queue(CraftingJob {
+                                    recipe: recipe.clone(),
+                                    target: JobTarget::Repeat(1),
+                                }));
+                            }
+                        } else {
+                            let (item, _) = recipe.output();
+                            resp.on_disabled_hover_text(format!(
+                                "Craft {} by hand to automate ({}/{} crafts)",
+                                item.data().name,
+                                progression.crafted[item],
+                                recipe.automation_crafts
+                            ));
+                        }
+                    });
             };
 
-            if let Some(recipe) = &crafter.recipe {
-                // Click existing recipe to change
-                let resp = draw_recipe(ui, recipe, icon_size, font_size);
-                Popup::menu(&resp).show(recipe_menu);
-            } else {
-                // Click button to select
-                ui.allocate_ui(Vec2::new(window_size.x, icon_size), |ui| {
-                    ui.menu_button("Select Recipe", recipe_menu);
+            let active = crafter.active_job();
+            ui.horizontal(|ui| {
+                ui.label(match (crafter.paused, active) {
+                    (true, _) => "Paused",
+                    (false, Some(_)) => "Running",
+                    (false, None) => "Idle",
                 });
-            }
+                if ui
+                    .button(if crafter.paused { "Resume" } else { "Pause" })
+                    .clicked()
+                {
+                    update(CrafterUpdate::SetPaused(!crafter.paused));
+                }
+                ui.menu_button("Add Job", recipe_menu);
+            });
+
+            // Job queue
+            crafter.queue.iter().enumerate().for_each(|(i, job)| {
+                ui.horizontal(|ui| {
+                    let resp = draw_recipe(ui, &job.recipe, icon_size, font_size);
+                    if active == Some(i) {
+                        resp.highlight();
+                    }
+
+                    let (mut stock, mut count) = match job.target {
+                        JobTarget::Repeat(count) => (false, count),
+                        JobTarget::Stock(count) => (true, count),
+                    };
+                    ComboBox::from_id_salt(("job target", i))
+                        .selected_text(if stock { "Until stock" } else { "Repeat" })
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut stock, false, "Repeat");
+                            ui.selectable_value(&mut stock, true, "Until stock");
+                        });
+                    ui.add(DragValue::new(&mut count).range(1..=999));
+
+                    let target = if stock {
+                        JobTarget::Stock(count)
+                    } else {
+                        JobTarget::Repeat(count)
+                    };
+                    if target != job.target {
+                        update(CrafterUpdate::SetTarget(i, target));
+                    }
+
+                    if i > 0 && ui.small_button("^").clicked() {
+                        update(CrafterUpdate::MoveUp(i));
+                    }
+                    if ui.small_button("x").clicked() {
+                        update(CrafterUpdate::Remove(i));
+                    }
+                });
+            });
 
             ui.separator();
 
             // Progress Bar
-            let progress = if let Some(recipe) = &crafter.recipe {
+            let progress = if let Some(recipe) = crafter.recipe() {
                 (crafter.crafting_juice / recipe.crafting_juice_cost).clamp(0., 1.)
             } else {
                 0.
             };
             draw_progress_bar(ui, window_size.x, font_size, progress);
+            draw_network_status(ui, &node.0);
+
+            // Input & output buffers, each taken out of through its own side
+            [
+                ("Input", &crafter.input, Side::Up),
+                ("Output", &crafter.output, Side::Down),
+            ]
+            .into_iter()
+            .for_each(|(name, container, side)| {
+                ui.label(name);
+                draw_capacity(ui, container);
+                draw_block_items(ui, name, container, block_pos, Some(side));
+            });
+        });
+}
 
-            // Storage
-            draw_item_grid(ui, "crafter", &container.items, icon_size)
-                .into_iter()
-                // Filter out responses that weren't drawn
-                .filter_map(|(id, resp)| resp.map(|resp| (id, resp)))
-                .for_each(|(id, resp)| {
-                    // Detect keypresses
-                    if resp.hovered() {
-                        use egui::Key::*;
-                        // Item transfer
-                        if ui.input(|i| i.key_pressed(T)) {
-                            MESSAGE_QUEUE.send(Message::TransferItemRequest(
-                                TransferItemRequestMessage {
-                                    item: id,
-                                    count: 1,
-                                    source: TransferItemSource::Block(block_pos.clone()),
-                                },
-                            ));
-                        }
+pub fn draw_hopper(ctx: &egui::Context, entity: EntityRef<'_>) {
+    let mut entity = entity.query::<(&BlockPos, &Hopper)>();
+    let (block_pos, hopper) = entity
+        .get()
+        .expect("Hopper doesn't have the right components!");
+
+    let items = ITEMS.get().expect("Item info not initialised!");
+
+    // Edit a copy, then send it off if anything changed
+    let mut from = hopper.from;
+    let mut to = hopper.to;
+    let mut filter = hopper.filter.clone();
+
+    Window::new("Hopper").resizable(false).show(ctx, |ui| {
+        // Which way items flow
+        [("From", &mut from), ("To", &mut to)]
+            .into_iter()
+            .for_each(|(label, side)| {
+                ComboBox::from_label(label)
+                    .selected_text(format!("{side:?}"))
+                    .show_ui(ui, |ui| {
+                        Side::ALL.into_iter().for_each(|s| {
+                            ui.selectable_value(side, s, format!("{s:?}"));
+                        });
+                    });
+            });
+
+        ui.separator();
+
+        // Item filter, nothing selected lets everything through
+        ui.label(if filter.is_empty() {
+            "Filter: anything"
+        } else {
+            "Filter:"
+        });
+        ui.horizontal_wrapped(|ui| {
+            items.iter().for_each(|(item, info)| {
+                let selected = filter.contains(&item);
+                if ui.selectable_label(selected, info.data.name).clicked() {
+                    if selected {
+                        filter.retain(|i| *i != item);
+                    } else {
+                        filter.push(item);
                     }
+                }
+            });
+        });
+    });
+
+    if from != hopper.from || to != hopper.to || filter != hopper.filter {
+        MESSAGE_QUEUE.send(Message::SetHopperConfig(SetHopperConfigMessage {
+            block: block_pos.clone(),
+            from,
+            to,
+            filter,
+        }));
+    }
+}
+
+pub fn draw_conveyor(ctx: &egui::Context, entity: EntityRef<'_>) {
+    let mut entity = entity.query::<(&BlockPos, &Conveyor)>();
+    let (block_pos, conveyor) = entity
+        .get()
+        .expect("Conveyor doesn't have the right components!");
+
+    let mut direction = conveyor.direction;
+
+    Window::new("Conveyor").resizable(false).show(ctx, |ui| {
+        ComboBox::from_label("Direction")
+            .selected_text(format!("{direction:?}"))
+            .show_ui(ui, |ui| {
+                [Side::North, Side::South, Side::East, Side::West]
+                    .into_iter()
+                    .for_each(|s| {
+                        ui.selectable_value(&mut direction, s, format!("{s:?}"));
+                    });
+            });
+
+        ui.separator();
+
+        // Items waiting to go on the belt
+        ui.label("Waiting");
+        draw_block_items(ui, "conveyor", &conveyor.input, block_pos, None);
+    });
+
+    if direction != conveyor.direction {
+        MESSAGE_QUEUE.send(Message::SetConveyorDirection(SetConveyorDirectionMessage {
+            block: block_pos.clone(),
+            direction,
+        }));
+    }
+}
+
+pub fn draw_generator(ctx: &egui::Context, entity: EntityRef<'_>) {
+    let mut entity = entity.query::<(&BlockPos, &Generator, &PowerNode)>();
+    let (block_pos, generator, node) = entity
+        .get()
+        .expect("Generator doesn't have the right components!");
+
+    let width = 256.;
+    let font_size = 16.;
+
+    Window::new("Generator").resizable(false).show(ctx, |ui| {
+        ui.label(format!("Output: {:.1} juice/s", generator.juice_per_second));
+
+        if let Some(burner) = &generator.burner {
+            draw_burner(ui, burner, block_pos, None, width, font_size);
+        }
+
+        ui.separator();
+        draw_network_status(ui, &node.0);
+    });
+}
+
+pub fn draw_conduit(ctx: &egui::Context, entity: EntityRef<'_>) {
+    let node = entity
+        .get::<&PowerNode>()
+        .expect("Conduit doesn't have the right components!");
+
+    Window::new("Conduit").resizable(false).show(ctx, |ui| {
+        draw_network_status(ui, &node.0);
+    });
+}
+
+pub fn draw_chunk_loader(ctx: &egui::Context, entity: EntityRef<'_>) {
+    let mut entity = entity.query::<(&BlockPos, &ChunkLoader)>();
+    let (block_pos, loader) = entity
+        .get()
+        .expect("Chunk loader doesn't have the right components!");
+
+    Window::new("Chunk Loader")
+        .resizable(false)
+        .show(ctx, |ui| {
+            match &loader.error {
+                Some(error) => {
+                    ui.colored_label(Color32::LIGHT_RED, format!("Not loading: {error}"))
+                }
+                None => ui.label("Keeping these chunks loaded:"),
+            };
+
+            if loader.error.is_none() {
+                ChunkLoader::chunks(block_pos).iter().for_each(|chunk| {
+                    ui.label(format!("{}, {}, {}", chunk.0.x, chunk.0.y, chunk.0.z));
                 });
+            }
+        });
+}
+
+pub fn draw_furnace(ctx: &egui::Context, entity: EntityRef<'_>) {
+    let mut entity = entity.query::<(&BlockPos, &Furnace)>();
+    let (block_pos, furnace) = entity
+        .get()
+        .expect("Furnace doesn't have the right components!");
+
+    let width = 256.;
+    let font_size = 16.;
+
+    Window::new("Furnace").resizable(false).show(ctx, |ui| {
+        draw_burner(
+            ui,
+            &furnace.burner,
+            block_pos,
+            Some(Side::North),
+            width,
+            font_size,
+        );
+
+        ui.separator();
+
+        // Cook progress
+        match furnace.recipe {
+            Some(recipe) => {
+                let (item, count) = recipe.output;
+                ui.label(format!("Cooking: {count}x {}", item.data().name));
+                draw_progress_bar(
+                    ui,
+                    width,
+                    font_size,
+                    (furnace.cook_time / recipe.cook_time).clamp(0., 1.),
+                );
+            }
+            None => {
+                ui.label("Nothing to cook");
+                draw_progress_bar(ui, width, font_size, 0.);
+            }
+        }
+
+        ui.label("Input");
+        draw_block_items(
+            ui,
+            "furnace input",
+            &furnace.input,
+            block_pos,
+            Some(Side::Up),
+        );
+        ui.label("Output");
+        draw_block_items(
+            ui,
+            "furnace output",
+            &furnace.output,
+            block_pos,
+            Some(Side::Down),
+        );
+    });
+}
+
+/// Fuel remaining & the fuel slot of something which burns items.
+/// `side` is the side of the block the fuel is taken out through.
+fn draw_burner(
+    ui: &mut Ui,
+    burner: &Burner,
+    block_pos: &BlockPos,
+    side: Option<Side>,
+    width: f32,
+    font_size: f32,
+) {
+    ui.label(format!("Fuel: {:.1}s", burner.burn_time));
+    draw_progress_bar(ui, width, font_size, burner.remaining());
+    draw_block_items(ui, "fuel", &burner.fuel, block_pos, side);
+}
+
+/// Items held by a block, which can be taken out with T or dragged to the inventory.
+/// `side` picks which of the block's buffers they're taken from, None for its default.
+fn draw_block_items(
+    ui: &mut Ui,
+    id: &str,
+    inventory: &Inventory,
+    block_pos: &BlockPos,
+    side: Option<Side>,
+) {
+    let source = TransferItemSource::Block(block_pos.clone());
+
+    draw_drop_zone(ui, &source, |ui| {
+        draw_item_grid(ui, id, inventory.items.iter().map(|(i, c)| (i, *c)), 32.)
+            .into_iter()
+            // Filter out responses that weren't drawn
+            .filter_map(|(id, resp)| resp.map(|resp| (id, resp)))
+            .for_each(|(id, resp)| {
+                draw_instance_tooltip(&resp, inventory, id);
+                handle_item_transfer(ui, &resp, inventory, id, &source, side, 1);
+            });
+    });
+}
+
+/// Search box, sort order & category tabs for an item window.
+/// Changes are saved on the player, so each window remembers its own.
+fn draw_item_view_controls(ui: &mut Ui, window: &str, player: EntityRef<'_>) -> ItemView {
+    let current = player
+        .get::<&ItemViews>()
+        .map(|views| views.get(window))
+        .unwrap_or_default();
+    let mut view = current.clone();
+
+    ui.horizontal(|ui| {
+        ui.add(
+            TextEdit::singleline(&mut view.search)
+                .hint_text("Search")
+                .desired_width(120.),
+        );
+        ComboBox::from_id_salt((window, "sort"))
+            .selected_text(view.sort.name())
+            .show_ui(ui, |ui| {
+                ItemSort::ALL.into_iter().for_each(|sort| {
+                    ui.selectable_value(&mut view.sort, sort, sort.name());
+                });
+            });
+    });
+
+    ui.horizontal(|ui| {
+        ui.selectable_value(&mut view.category, None, "All");
+        ItemCategory::ALL.into_iter().for_each(|category| {
+            ui.selectable_value(&mut view.category, Some(category), category.name());
+        });
+    });
+
+    if view != current {
+        MESSAGE_QUEUE.send(Message::SetItemView(SetItemViewMessage {
+            window: window.to_string(),
+            view: view.clone(),
+        }));
+    }
+
+    view
+}
+
+/// How many of an item a transfer moves, picked by holding a modifier key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TransferAmount {
+    /// The amount typed into the window
+    Typed,
+    /// One full stack (Shift)
+    Stack,
+    /// Half of what's there (Ctrl)
+    Half,
+    /// All of the item (Alt)
+    All,
+}
+
+impl TransferAmount {
+    fn from_modifiers(modifiers: &Modifiers) -> Self {
+        if modifiers.alt {
+            TransferAmount::All
+        } else if modifiers.shift {
+            TransferAmount::Stack
+        } else if modifiers.command {
+            TransferAmount::Half
+        } else {
+            TransferAmount::Typed
+        }
+    }
+
+    fn count(&self, inventory: &Inventory, item: ItemType, typed: usize) -> usize {
+        let held = inventory.items[item];
+        match self {
+            TransferAmount::Typed => typed.min(held),
+            TransferAmount::Stack => inventory.stack_size(item).min(held),
+            TransferAmount::Half => held.div_ceil(2),
+            TransferAmount::All => held,
+        }
+    }
+}
+
+/// Items being dragged from one window to another
+struct DraggedItems {
+    item: ItemType,
+    count: usize,
+    source: TransferItemSource,
+    source_side: Option<Side>,
+}
+
+fn request_transfer(
+    item: ItemType,
+    count: usize,
+    source: &TransferItemSource,
+    source_side: Option<Side>,
+) {
+    if count == 0 {
+        return;
+    }
+
+    MESSAGE_QUEUE.send(Message::TransferItemRequest(TransferItemRequestMessage {
+        item,
+        count,
+        source: source.clone(),
+        source_side,
+    }));
+}
+
+/// Amount moved by each T press, and a button to move everything at once
+fn draw_transfer_controls(
+    ui: &mut Ui,
+    inventory: &Inventory,
+    source: &TransferItemSource,
+) -> usize {
+    let id = ui.id().with("transfer amount");
+    let mut typed = ui.data_mut(|d| *d.get_temp_mut_or(id, 1_usize));
+
+    ui.horizontal(|ui| {
+        ui.label("Move")
+            .on_hover_text("T moves this many, Shift+T a stack, Ctrl+T half, Alt+T all");
+        ui.add(DragValue::new(&mut typed).range(1..=9999));
+
+        if ui.button("Move everything").clicked() {
+            inventory
+                .items
+                .iter()
+                .for_each(|(item, count)| request_transfer(item, *count, source, None));
+        }
+    });
+
+    ui.data_mut(|d| d.insert_temp(id, typed));
+    typed
+}
+
+/// Move the item under an icon with T, or let it be dragged to another window.
+/// Holding a modifier picks how many are moved, see `TransferAmount`.
+fn handle_item_transfer(
+    ui: &Ui,
+    resp: &Response,
+    inventory: &Inventory,
+    item: ItemType,
+    source: &TransferItemSource,
+    source_side: Option<Side>,
+    typed: usize,
+) {
+    let modifiers = ui.input(|i| i.modifiers);
+    let count = TransferAmount::from_modifiers(&modifiers).count(inventory, item, typed);
+
+    if resp.hovered() && ui.input(|i| i.key_pressed(egui::Key::T)) {
+        request_transfer(item, count, source, source_side);
+    }
+
+    resp.interact(Sense::drag())
+        .dnd_set_drag_payload(DraggedItems {
+            item,
+            count,
+            source: source.clone(),
+            source_side,
         });
 }
 
+/// Accept items dragged in from another window
+fn draw_drop_zone(ui: &mut Ui, source: &TransferItemSource, add_contents: impl FnOnce(&mut Ui)) {
+    let (_, dropped) = ui.dnd_drop_zone::<DraggedItems, _>(Frame::new(), add_contents);
+
+    if let Some(dropped) = dropped
+        && dropped.source != *source
+    {
+        request_transfer(
+            dropped.item,
+            dropped.count,
+            &dropped.source,
+            dropped.source_side,
+        );
+    }
+}
+
 /// Displayer the inventory for a given entity
 pub fn draw_inventory(ctx: &egui::Context, entity: EntityRef<'_>) {
     let inventory = entity
-        .get::<&Container>()
+        .get::<&Inventory>()
         .expect("Failed to get container for entity");
 
     let icon_size = 32.;
@@ -167,50 +768,57 @@ pub fn draw_inventory(ctx: &egui::Context, entity: EntityRef<'_>) {
         .scroll_bar_visibility(ScrollBarVisibility::VisibleWhenNeeded)
         .default_width(window_size.x)
         .show(ctx, |ui| {
-            draw_item_grid(ui, "inventory", &inventory.items, icon_size)
-                .into_iter()
-                // Filter out responses that weren't drawn
-                .filter_map(|(id, resp)| resp.map(|resp| (id, resp)))
-                .for_each(|(id, resp)| {
-                    // Detect keypresses
-                    if resp.hovered() {
-                        use egui::Key::*;
-                        // Hotbar assignment
-                        [Num1, Num2, Num3, Num4, Num5, Num6, Num7, Num8, Num9, Num0]
-                            .into_iter()
-                            .enumerate()
-                            .for_each(|(slot, key)| {
-                                if ui.input(|i| i.key_pressed(key)) {
-                                    MESSAGE_QUEUE.send(Message::ItemFavourited(
-                                        ItemFavouritedMessage { item: id, slot },
-                                    ));
-                                }
-                            });
-
-                        // Item transfer
-                        if ui.input(|i| i.key_pressed(T)) {
-                            MESSAGE_QUEUE.send(Message::TransferItemRequest(
-                                TransferItemRequestMessage {
-                                    item: id,
-                                    count: 1,
-                                    source: TransferItemSource::Inventory,
-                                },
-                            ));
+            let source = TransferItemSource::Inventory;
+
+            draw_capacity(ui, &inventory);
+            let typed = draw_transfer_controls(ui, &inventory, &source);
+            let view = draw_item_view_controls(ui, "Inventory", entity);
+            draw_drop_zone(ui, &source, |ui| {
+                draw_item_grid(ui, "inventory", view.apply(&inventory.items), icon_size)
+                    .into_iter()
+                    // Filter out responses that weren't drawn
+                    .filter_map(|(id, resp)| resp.map(|resp| (id, resp)))
+                    .for_each(|(id, resp)| {
+                        draw_instance_tooltip(&resp, &inventory, id);
+                        handle_item_transfer(ui, &resp, &inventory, id, &source, None, typed);
+
+                        // Detect keypresses
+                        if resp.hovered() {
+                            use egui::Key::*;
+                            // Hotbar assignment
+                            [Num1, Num2, Num3, Num4, Num5, Num6, Num7, Num8, Num9, Num0]
+                                .into_iter()
+                                .enumerate()
+                                .for_each(|(slot, key)| {
+                                    if ui.input(|i| i.key_pressed(key)) {
+                                        MESSAGE_QUEUE.send(Message::ItemFavourited(
+                                            ItemFavouritedMessage { item: id, slot },
+                                        ));
+                                    }
+                                });
                         }
-                    }
-                });
+                    });
+            });
         });
 }
 
 pub fn draw_crafting_window(ctx: &egui::Context, entity: EntityRef<'_>) {
-    let mut inventory = entity
-        .get::<&mut Container>()
-        .expect("Failed to get container for entity");
+    let mut query = entity.query::<(&mut Inventory, &mut Progression)>();
+    let (inventory, progression) = query.get().expect("Failed to get container for entity");
 
     let icon_size = 32.;
     let font_size = 15.;
 
-    let recipes = inventory.get_craftable_recipes().collect::<Vec<_>>();
+    let recipes = inventory
+        .get_craftable_recipes()
+        .filter(|r| progression.is_unlocked(r))
+        .collect::<Vec<_>>();
+
+    // Show what's needed for the next recipes
+    let locked_recipes = RECIPES
+        .iter()
+        .filter(|r| !progression.is_unlocked(r))
+        .collect::<Vec<_>>();
 
     Window::new("Crafting")
         .default_open(false)
@@ -221,14 +829,33 @@ pub fn draw_crafting_window(ctx: &egui::Context, entity: EntityRef<'_>) {
 
                 // Craft on click
                 if resp.clicked() {
-                    inventory.craft_recipe(r);
+                    inventory.craft_recipe(r, &mut rand::rng());
+                    progression.record_craft(r);
                 }
             });
+
+            if !locked_recipes.is_empty() {
+                ui.collapsing("Locked", |ui| {
+                    locked_recipes.iter().for_each(|r| {
+                        let requirements = r
+                            .unlock
+                            .iter()
+                            .filter(|c| !progression.is_met(c))
+                            .map(|c| c.to_string())
+                            .collect::<Vec<_>>()
+                            .join("\n");
+
+                        ui.add_enabled_ui(false, |ui| draw_recipe(ui, r, icon_size, font_size))
+                            .inner
+                            .on_disabled_hover_text(requirements);
+                    });
+                });
+            }
         });
 }
 
 pub fn draw_hotbar(ctx: &egui::Context, entity: EntityRef<'_>) {
-    let mut query = entity.query::<(&Container, &Hotbar)>();
+    let mut query = entity.query::<(&Inventory, &Hotbar)>();
     let (inventory, hotbar) = query.get().unwrap();
 
     let icon_size = 32.;
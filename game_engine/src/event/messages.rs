@@ -1,15 +1,36 @@
 use hecs::Entity;
 
 use crate::{
-    data::{block::BlockType, item::ItemType, recipe::Recipe},
-    entity::components::EntityType,
+    container::{Side, TransferError},
+    data::{block::BlockType, entity::EntityType, item::ItemType},
+    entity::components::{CrafterUpdate, ItemFilter, ItemView},
     state::world::{BlockPos, WorldPos},
 };
 
 #[derive(Debug)]
-pub struct SetCraftingRecipeMessage {
+pub struct UpdateCrafterMessage {
     pub block: BlockPos,
-    pub recipe: Recipe,
+    pub update: CrafterUpdate,
+}
+
+#[derive(Debug)]
+pub struct SetHopperConfigMessage {
+    pub block: BlockPos,
+    pub from: Side,
+    pub to: Side,
+    pub filter: Vec<ItemType>,
+}
+
+#[derive(Debug)]
+pub struct SetItemFilterMessage {
+    pub block: BlockPos,
+    pub filter: ItemFilter,
+}
+
+#[derive(Debug)]
+pub struct SetConveyorDirectionMessage {
+    pub block: BlockPos,
+    pub direction: Side,
 }
 
 #[derive(Debug)]
@@ -18,7 +39,14 @@ pub struct ItemFavouritedMessage {
     pub slot: usize,
 }
 
-#[derive(Debug, Clone)]
+/// The player changed how an item window is searched, sorted or filtered
+#[derive(Debug)]
+pub struct SetItemViewMessage {
+    pub window: String,
+    pub view: ItemView,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum TransferItemSource {
     Inventory,
     Block(BlockPos),
@@ -29,6 +57,8 @@ pub struct TransferItemRequestMessage {
     pub item: ItemType,
     pub count: usize,
     pub source: TransferItemSource,
+    /// Side of the source block to take from, picking between its buffers. None == its default.
+    pub source_side: Option<Side>,
 }
 
 #[derive(Debug)]
@@ -37,12 +67,25 @@ pub struct SpawnEntityMessage {
     pub entity_type: EntityType,
 }
 
+/// A transfer was dropped because no items could be moved
+#[derive(Debug, Clone)]
+pub struct TransferFailedMessage {
+    pub source: Entity,
+    pub dest: Entity,
+    pub item: ItemType,
+    pub reason: TransferError,
+}
+
 #[derive(Debug)]
 pub struct TransferItemMessage {
     pub source: Entity,
     pub dest: Entity,
     pub item: ItemType,
     pub count: usize,
+    /// Side of the source block items are taken out through, None for direct access
+    pub source_side: Option<Side>,
+    /// Side of the dest block items are put in through, None for direct access
+    pub dest_side: Option<Side>,
 }
 
 #[derive(Debug)]
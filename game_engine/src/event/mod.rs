@@ -9,8 +9,10 @@ use crate::{
     InteractionMode,
     entity::components::UprightOrientation,
     event::messages::{
-        BlockChangedMessage, ItemFavouritedMessage, PlaceBlockMessage, SetCraftingRecipeMessage,
-        SpawnEntityMessage, TransferItemMessage, TransferItemRequestMessage,
+        BlockChangedMessage, ItemFavouritedMessage, PlaceBlockMessage, SetConveyorDirectionMessage,
+        SetHopperConfigMessage, SetItemFilterMessage, SetItemViewMessage, SpawnEntityMessage,
+        TransferFailedMessage, TransferItemMessage, TransferItemRequestMessage,
+        UpdateCrafterMessage,
     },
     state::world::{BlockPos, WorldPos},
 };
@@ -21,6 +23,7 @@ pub enum Message {
     ItemFavourited(ItemFavouritedMessage),
     BlockChanged(BlockChangedMessage),
     PlayerMoved((WorldPos, UprightOrientation)),
+    TransferFailed(TransferFailedMessage),
 
     // Action messages - Do this thing
     // It's assumed that at the action has been validated at this point
@@ -28,7 +31,11 @@ pub enum Message {
     SetInteractionMode(InteractionMode),
     BreakBlock(BlockPos),
     PlaceBlock(PlaceBlockMessage),
-    SetCraftingRecipe(SetCraftingRecipeMessage),
+    UpdateCrafter(UpdateCrafterMessage),
+    SetHopperConfig(SetHopperConfigMessage),
+    SetItemFilter(SetItemFilterMessage),
+    SetItemView(SetItemViewMessage),
+    SetConveyorDirection(SetConveyorDirectionMessage),
 
     // Transfer an item from the player's inventory to whatever interface is open
     TransferItemRequest(TransferItemRequestMessage),
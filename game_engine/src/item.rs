@@ -0,0 +1,275 @@
+use crate::data::{
+    block::BlockData,
+    item::{ItemType, ToolKind},
+};
+
+/// Enchant-like modifiers which can be applied to individual items
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemModifier {
+    /// Increases the breaking strength of a tool
+    Efficiency(u32),
+    /// Increases the maximum durability of a tool
+    Reinforced(u32),
+}
+
+/// A single non-stackable item, carrying its own data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemInstance {
+    pub item_type: ItemType,
+    /// Remaining uses before the item breaks
+    pub durability: Option<u32>,
+    /// Player-given name, overrides the item's default name
+    pub name: Option<String>,
+    pub modifiers: Vec<ItemModifier>,
+}
+
+impl ItemInstance {
+    /// Create a fresh instance of an item
+    pub fn new(item_type: ItemType) -> Self {
+        Self {
+            item_type,
+            durability: item_type.data().durability,
+            name: None,
+            modifiers: vec![],
+        }
+    }
+
+    /// Add a modifier to this item. Durability gained from the modifier is applied immediately.
+    pub fn add_modifier(&mut self, modifier: ItemModifier) {
+        if let ItemModifier::Reinforced(amount) = modifier
+            && let Some(durability) = &mut self.durability
+        {
+            *durability += amount;
+        }
+
+        self.modifiers.push(modifier);
+    }
+
+    /// Name shown to the player
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or(self.item_type.data().name)
+    }
+
+    /// Maximum durability including modifiers
+    pub fn max_durability(&self) -> Option<u32> {
+        let bonus = self
+            .modifiers
+            .iter()
+            .map(|m| match m {
+                ItemModifier::Reinforced(amount) => *amount,
+                _ => 0,
+            })
+            .sum::<u32>();
+
+        self.item_type.data().durability.map(|d| d + bonus)
+    }
+
+    /// Block breaking strength including modifiers
+    pub fn breaking_strength(&self) -> Option<u32> {
+        let bonus = self
+            .modifiers
+            .iter()
+            .map(|m| match m {
+                ItemModifier::Efficiency(amount) => *amount,
+                _ => 0,
+            })
+            .sum::<u32>();
+
+        self.item_type.data().breaking_strength.map(|s| s + bonus)
+    }
+
+    /// Wear down the item by the given amount. Returns true if the item has broken.
+    pub fn wear(&mut self, amount: u32) -> bool {
+        let Some(durability) = &mut self.durability else {
+            return false;
+        };
+
+        *durability = durability.saturating_sub(amount);
+        *durability == 0
+    }
+}
+
+/// Items moving as one unit, eg. between containers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ItemStack {
+    /// Interchangeable items, only the count is tracked
+    Counted(ItemType, usize),
+    /// A single item with its own data
+    Unique(ItemInstance),
+}
+
+impl ItemStack {
+    #[inline]
+    pub fn item_type(&self) -> ItemType {
+        match self {
+            ItemStack::Counted(item, _) => *item,
+            ItemStack::Unique(instance) => instance.item_type,
+        }
+    }
+
+    #[inline]
+    pub fn count(&self) -> usize {
+        match self {
+            ItemStack::Counted(_, count) => *count,
+            ItemStack::Unique(_) => 1,
+        }
+    }
+}
+
+/// What an entity is able to do to blocks with the item it's holding
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MiningCapability {
+    pub breaking_strength: u32,
+    /// None when not holding a tool
+    pub tool: Option<ToolKind>,
+}
+
+/// How mining a particular block would go
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlockMining {
+    /// Seconds taken to break the block
+    pub break_time: f32,
+    /// Whether the block's item is given when it breaks. Only a tool of the wrong kind loses the
+    /// drop, bare hands still get it.
+    pub drops: bool,
+}
+
+impl MiningCapability {
+    /// Whether the held tool is what the block should be mined with
+    pub fn is_right_tool(&self, block: &BlockData) -> bool {
+        block
+            .preferred_tool
+            .is_none_or(|kind| self.tool.is_some_and(|t| t.is_effective_as(kind)))
+    }
+
+    /// Work out how mining the block would go. None if the block is too hard.
+    pub fn mine(&self, block: &BlockData) -> Option<BlockMining> {
+        // Even the softest blocks take a moment to break
+        let min_break_time = 0.2;
+        // Time taken when the block is as hard as the tool is strong
+        let matched_break_time = 1.;
+        // The wrong tool takes this many times longer
+        let wrong_tool_slowdown = 5.;
+
+        let hardness = block.hardness.filter(|h| *h <= self.breaking_strength)?;
+        let ratio = hardness as f32 / self.breaking_strength.max(1) as f32;
+        let right_tool = self.is_right_tool(block);
+
+        let mut break_time = min_break_time + matched_break_time * ratio;
+        if !right_tool {
+            break_time *= wrong_tool_slowdown;
+        }
+
+        Some(BlockMining {
+            break_time,
+            drops: right_tool || self.tool.is_none(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        data::{
+            block::BlockType,
+            item::{ItemType, ToolKind},
+        },
+        entity::components::Inventory,
+        item::{ItemInstance, ItemModifier, ItemStack, MiningCapability},
+    };
+
+    #[test]
+    fn test_tool_wear() {
+        let mut pickaxe = ItemInstance::new(ItemType::CopperPickaxe);
+        let max = pickaxe.max_durability().unwrap();
+
+        assert!(!pickaxe.wear(max - 1));
+        assert!(pickaxe.wear(1));
+
+        // Stackable items never break
+        let mut stone = ItemInstance::new(ItemType::Stone);
+        assert!(!stone.wear(1000));
+    }
+
+    #[test]
+    fn test_modifiers() {
+        let mut pickaxe = ItemInstance::new(ItemType::CopperPickaxe);
+        let base_strength = pickaxe.breaking_strength().unwrap();
+        let base_durability = pickaxe.durability.unwrap();
+
+        pickaxe.add_modifier(ItemModifier::Efficiency(20));
+        pickaxe.add_modifier(ItemModifier::Reinforced(10));
+
+        assert_eq!(pickaxe.breaking_strength(), Some(base_strength + 20));
+        assert_eq!(pickaxe.durability, Some(base_durability + 10));
+        assert_eq!(pickaxe.max_durability(), Some(base_durability + 10));
+    }
+
+    #[test]
+    fn test_container_instances() {
+        let mut container = Inventory::default();
+        container.add_item(ItemType::Stone, 10);
+        container.add_item(ItemType::CopperPickaxe, 2);
+
+        // Stackable items stay compact
+        assert_eq!(container.items[ItemType::Stone], 10);
+        assert_eq!(container.instances.len(), 2);
+
+        // Wear the pickaxe in use until it breaks
+        let durability = ItemType::CopperPickaxe.data().durability.unwrap();
+        for _ in 0..durability {
+            container.wear_item(ItemType::CopperPickaxe, 1);
+        }
+        assert_eq!(container.items[ItemType::CopperPickaxe], 1);
+        assert_eq!(container.instances.len(), 1);
+
+        // Moving unique items keeps their data
+        let stacks = container.take_items(ItemType::CopperPickaxe, 1);
+        assert_eq!(container.items[ItemType::CopperPickaxe], 0);
+        assert!(matches!(
+            stacks.as_slice(),
+            [ItemStack::Unique(ItemInstance { durability: Some(d), .. })] if *d == durability
+        ));
+    }
+
+    #[test]
+    fn test_mining_capability() {
+        let hands = MiningCapability::default();
+        let pickaxe = MiningCapability {
+            breaking_strength: 100,
+            tool: Some(ToolKind::Pickaxe),
+        };
+        let shovel = MiningCapability {
+            breaking_strength: 100,
+            tool: Some(ToolKind::Shovel),
+        };
+        let drill = MiningCapability {
+            breaking_strength: 500,
+            tool: Some(ToolKind::Drill),
+        };
+
+        let stone = BlockType::Stone.data();
+        let dirt = BlockType::Dirt.data();
+
+        // Right tool drops the block's item
+        let right = pickaxe.mine(stone).unwrap();
+        assert!(right.drops);
+
+        // Wrong tool is slower & drops nothing
+        let wrong = shovel.mine(stone).unwrap();
+        assert!(!wrong.drops);
+        assert!(wrong.break_time > right.break_time);
+
+        // Bare hands can still dig soft blocks, slowly
+        let by_hand = hands.mine(dirt).unwrap();
+        assert!(by_hand.drops);
+        assert!(by_hand.break_time > shovel.mine(dirt).unwrap().break_time);
+        assert!(hands.mine(BlockType::Chest.data()).unwrap().drops);
+        assert!(hands.mine(stone).is_none());
+
+        // Drills do the job of pickaxes & shovels
+        assert!(drill.mine(stone).unwrap().drops);
+        assert!(drill.mine(dirt).unwrap().drops);
+        assert!(!drill.mine(BlockType::Chest.data()).unwrap().drops);
+    }
+}
@@ -9,15 +9,22 @@ extern crate impl_ops;
 
 use crate::state::world::BlockPos;
 
+pub mod behaviour;
 pub mod block;
 pub mod camera;
+pub mod container;
 pub mod data;
 pub mod entity;
 pub mod event;
+pub mod item;
 pub mod math;
+pub mod pathfinding;
 pub mod perlin_cdf;
+pub mod power;
 pub mod render;
 pub mod state;
+pub mod storage;
+pub mod tick;
 pub mod ui;
 pub mod util;
 pub mod world_gen;
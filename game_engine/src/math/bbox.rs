@@ -3,7 +3,7 @@ use cgmath::{BaseNum, EuclideanSpace, InnerSpace, Point3, Vector3, Zero};
 use super::ray::Ray;
 use crate::{math::ray::RayCollision, state::world::BlockPos};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AABB<S: BaseNum> {
     pub start: Point3<S>,
     pub end: Point3<S>,
@@ -0,0 +1,530 @@
+/*
+A* over the blocks entities can stand in. Searches are spread over frames with a shared budget of
+nodes, and finished paths are cached until a block along them changes.
+*/
+
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use cgmath::Vector3;
+use rustc_hash::FxHashMap;
+
+use crate::{
+    data::block::BlockType,
+    event::{Message, Subscriber, messages::BlockChangedMessage},
+    state::world::{BlockPos, World},
+};
+
+/// How an entity is able to get around
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Mobility {
+    /// Blocks of clearance needed to stand somewhere
+    pub height: u32,
+    /// Highest ledge it can climb onto in one step
+    pub step_up: u32,
+    /// Furthest it will drop down in one step
+    pub max_fall: u32,
+    /// Flyers don't need anything to stand on, and can go straight up & down
+    pub flying: bool,
+}
+
+impl Default for Mobility {
+    fn default() -> Self {
+        Self {
+            height: 2,
+            step_up: 1,
+            max_fall: 3,
+            flying: false,
+        }
+    }
+}
+
+/// Whether there's a block in the way at a position. Chunks which aren't loaded count as solid so
+/// nothing tries to walk into them.
+pub fn is_solid(world: &World, pos: &BlockPos) -> bool {
+    world
+        .get_block(pos)
+        .is_none_or(|block| block.block_type != BlockType::Air)
+}
+
+/// Whether an entity could be stood with its feet in this block
+pub fn can_stand(
+    pos: &BlockPos,
+    mobility: &Mobility,
+    is_solid: impl Fn(&BlockPos) -> bool,
+) -> bool {
+    Searcher {
+        mobility,
+        is_solid: &is_solid,
+    }
+    .walkable(pos)
+}
+
+/// Cost of moving one block sideways. Climbing & falling cost extra on top.
+const STEP_COST: u32 = 2;
+
+/// Finds paths through a world, described by which blocks are solid
+struct Searcher<'a, F: Fn(&BlockPos) -> bool> {
+    mobility: &'a Mobility,
+    is_solid: &'a F,
+}
+
+impl<F: Fn(&BlockPos) -> bool> Searcher<'_, F> {
+    /// Whether `count` blocks going up from `pos` are all empty
+    fn clear(&self, pos: &BlockPos, count: u32) -> bool {
+        (0..count as i32).all(|y| !(self.is_solid)(&(pos + Vector3::new(0, y, 0))))
+    }
+
+    /// Whether an entity could be stood (or hovering) with its feet in this block
+    fn walkable(&self, pos: &BlockPos) -> bool {
+        self.clear(pos, self.mobility.height)
+            && (self.mobility.flying || (self.is_solid)(&(pos - Vector3::unit_y())))
+    }
+
+    /// Places reachable in one step, with what it costs to get there
+    fn neighbours(&self, pos: &BlockPos) -> Vec<(BlockPos, u32)> {
+        let sideways = [
+            Vector3::unit_x(),
+            -Vector3::unit_x(),
+            Vector3::unit_z(),
+            -Vector3::unit_z(),
+        ];
+
+        if self.mobility.flying {
+            return sideways
+                .into_iter()
+                .chain([Vector3::unit_y(), -Vector3::unit_y()])
+                .map(|offset| pos + offset)
+                .filter(|next| self.walkable(next))
+                .map(|next| (next, STEP_COST))
+                .collect();
+        }
+
+        sideways
+            .into_iter()
+            .filter_map(|offset| self.step(pos, offset))
+            .collect()
+    }
+
+    /// Where walking off sideways ends up, climbing or dropping as needed
+    fn step(&self, pos: &BlockPos, offset: Vector3<i32>) -> Option<(BlockPos, u32)> {
+        let height = self.mobility.height;
+        let step_up = self.mobility.step_up as i32;
+        let max_fall = self.mobility.max_fall as i32;
+
+        // Only one height in a column can be stood on with ground under it & room above
+        (-max_fall..=step_up).rev().find_map(|dy| {
+            let next = pos + offset + Vector3::new(0, dy, 0);
+            let room = match dy {
+                // Headroom to climb up before stepping across
+                1.. => self.clear(&(pos + Vector3::new(0, height as i32, 0)), dy as u32),
+                // Nothing in the way all the way down
+                ..0 => self.clear(&next, height + dy.unsigned_abs()),
+                0 => true,
+            };
+
+            let cost = STEP_COST + dy.unsigned_abs();
+            (room && self.walkable(&next)).then_some((next, cost))
+        })
+    }
+
+    /// Lowest possible cost from one place to another, never more than the real cost
+    fn heuristic(&self, from: &BlockPos, to: &BlockPos) -> u32 {
+        let diff = to.0 - from.0;
+        let across = diff.x.unsigned_abs() + diff.z.unsigned_abs();
+        match self.mobility.flying {
+            true => (across + diff.y.unsigned_abs()) * STEP_COST,
+            false => across * STEP_COST,
+        }
+    }
+}
+
+/// Search which carries on over several frames
+#[derive(Debug, Default)]
+struct Search {
+    open: BinaryHeap<Reverse<(u32, u32, [i32; 3])>>,
+    costs: FxHashMap<BlockPos, u32>,
+    came_from: FxHashMap<BlockPos, BlockPos>,
+    expanded: usize,
+    /// Frame it was last asked for
+    asked: u64,
+}
+
+/// Finished path, remembered for whoever asks for it next
+#[derive(Debug)]
+struct CachedPath {
+    path: Vec<BlockPos>,
+    /// Frame it was last asked for
+    asked: u64,
+}
+
+/// How a path request is getting on
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathStatus {
+    /// Blocks to walk through in order, starting after the start
+    Found(Vec<BlockPos>),
+    /// Still searching, ask again next frame
+    Pending,
+    /// No way there, or it's too far to find
+    NoPath,
+}
+
+type PathKey = (BlockPos, BlockPos, Mobility);
+
+/// Works out paths for entities, sharing a budget between them each frame
+#[derive(Debug)]
+pub struct Pathfinder {
+    /// Most nodes expanded in one frame, any searches left over carry on next frame
+    pub budget: usize,
+    /// Most nodes one search expands before giving up
+    pub max_nodes: usize,
+    /// Most paths kept, the ones gone longest without being asked for are dropped first
+    pub max_cached: usize,
+    /// Frames before somewhere found to be unreachable is searched for again
+    pub retry_frames: u64,
+    frame: u64,
+    spent: usize,
+    searches: FxHashMap<PathKey, Search>,
+    paths: FxHashMap<PathKey, CachedPath>,
+    /// Frame each unreachable goal was given up on
+    failed: FxHashMap<PathKey, u64>,
+}
+
+impl Default for Pathfinder {
+    fn default() -> Self {
+        Self {
+            budget: 2048,
+            max_nodes: 8192,
+            max_cached: 256,
+            retry_frames: 60,
+            frame: 0,
+            spent: 0,
+            searches: Default::default(),
+            paths: Default::default(),
+            failed: Default::default(),
+        }
+    }
+}
+
+impl Pathfinder {
+    /// Start a new frame's worth of budget. Searches nobody asked after last frame are dropped.
+    pub fn new_frame(&mut self) {
+        self.spent = 0;
+        self.frame += 1;
+
+        let frame = self.frame;
+        self.searches.retain(|_, search| search.asked + 1 >= frame);
+        let retry_frames = self.retry_frames;
+        self.failed
+            .retain(|_, failed| *failed + retry_frames > frame);
+    }
+
+    /// Find a path for an entity with its feet in `start` to `goal`, carrying on where it left off
+    /// if it's been asked before
+    pub fn find_path(
+        &mut self,
+        start: &BlockPos,
+        goal: &BlockPos,
+        mobility: &Mobility,
+        is_solid: impl Fn(&BlockPos) -> bool,
+    ) -> PathStatus {
+        let key = (start.clone(), goal.clone(), *mobility);
+        if let Some(cached) = self.paths.get_mut(&key) {
+            cached.asked = self.frame;
+            return PathStatus::Found(cached.path.clone());
+        }
+        if self.failed.contains_key(&key) {
+            return PathStatus::NoPath;
+        }
+
+        let searcher = Searcher {
+            mobility,
+            is_solid: &is_solid,
+        };
+        if !searcher.walkable(goal) {
+            self.failed.insert(key, self.frame);
+            return PathStatus::NoPath;
+        }
+
+        let search = self.searches.entry(key.clone()).or_insert_with(|| {
+            let mut search = Search::default();
+            search.costs.insert(start.clone(), 0);
+            search.open.push(Reverse((
+                searcher.heuristic(start, goal),
+                0,
+                start.0.into(),
+            )));
+            search
+        });
+        search.asked = self.frame;
+
+        while self.spent < self.budget {
+            let Some(Reverse((_, cost, pos))) = search.open.pop() else {
+                self.searches.remove(&key);
+                self.failed.insert(key, self.frame);
+                return PathStatus::NoPath;
+            };
+            let pos = BlockPos(pos.into());
+
+            if pos == *goal {
+                let path = trace_path(&search.came_from, start, goal);
+                self.searches.remove(&key);
+                self.cache(key, path.clone());
+                return PathStatus::Found(path);
+            }
+            // Already got here a cheaper way
+            if search.costs.get(&pos).is_some_and(|best| *best < cost) {
+                continue;
+            }
+
+            self.spent += 1;
+            search.expanded += 1;
+            if search.expanded > self.max_nodes {
+                self.searches.remove(&key);
+                self.failed.insert(key, self.frame);
+                return PathStatus::NoPath;
+            }
+
+            for (next, step) in searcher.neighbours(&pos) {
+                let next_cost = cost + step;
+                if search
+                    .costs
+                    .get(&next)
+                    .is_some_and(|best| *best <= next_cost)
+                {
+                    continue;
+                }
+
+                search.costs.insert(next.clone(), next_cost);
+                search.came_from.insert(next.clone(), pos.clone());
+                search.open.push(Reverse((
+                    next_cost + searcher.heuristic(&next, goal),
+                    next_cost,
+                    next.0.into(),
+                )));
+            }
+        }
+
+        PathStatus::Pending
+    }
+
+    /// Remember a path, making room by dropping the one gone longest without being asked for
+    fn cache(&mut self, key: PathKey, path: Vec<BlockPos>) {
+        if self.paths.len() >= self.max_cached
+            && let Some(oldest) = self
+                .paths
+                .iter()
+                .min_by_key(|(_, cached)| cached.asked)
+                .map(|(key, _)| key.clone())
+        {
+            self.paths.remove(&oldest);
+        }
+
+        let asked = self.frame;
+        self.paths.insert(key, CachedPath { path, asked });
+    }
+
+    /// Forget cached paths which a changed block could be in the way of or holding up.
+    /// As well as the body & floor, that's the headroom above a node needed to climb or drop
+    /// between it & the next.
+    fn invalidate(&mut self, changed: &BlockPos) {
+        self.paths.retain(|(start, _, mobility), cached| {
+            let headroom = mobility.step_up.max(mobility.max_fall);
+            let above = (mobility.height + headroom) as i32;
+            !std::iter::once(start)
+                .chain(cached.path.iter())
+                .any(|node| {
+                    let diff = changed.0 - node.0;
+                    diff.x == 0 && diff.z == 0 && (-1..above).contains(&diff.y)
+                })
+        });
+
+        // Half-done searches might have already ruled out the block, so they start over,
+        // and it might have opened up a way to somewhere unreachable
+        self.searches.clear();
+        self.failed.clear();
+    }
+
+    /// Number of paths cached
+    pub fn cached(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// Nodes expanded so far this frame
+    pub fn spent(&self) -> usize {
+        self.spent
+    }
+}
+
+impl Subscriber for Pathfinder {
+    fn handle_message(&mut self, event: &Message) {
+        if let Message::BlockChanged(BlockChangedMessage { pos, .. }) = event {
+            self.invalidate(pos);
+        }
+    }
+}
+
+/// Walk back from the goal to get the path there
+fn trace_path(
+    came_from: &FxHashMap<BlockPos, BlockPos>,
+    start: &BlockPos,
+    goal: &BlockPos,
+) -> Vec<BlockPos> {
+    let mut path = vec![goal.clone()];
+    while let Some(prev) = came_from
+        .get(path.last().unwrap())
+        .filter(|prev| *prev != start)
+    {
+        path.push(prev.clone());
+    }
+
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_hash::FxHashSet;
+
+    use crate::{
+        data::block::BlockType,
+        event::{Message, Subscriber, messages::BlockChangedMessage},
+        pathfinding::{Mobility, PathStatus, Pathfinder},
+        state::world::BlockPos,
+    };
+
+    /// Floor along y = -1 from x = 0 to 9, with a wall at x = 5
+    fn world(wall_height: i32) -> FxHashSet<BlockPos> {
+        let floor = (0..10).map(|x| BlockPos::new(x, -1, 0));
+        let wall = (0..wall_height).map(|y| BlockPos::new(5, y, 0));
+        floor.chain(wall).collect()
+    }
+
+    fn find(
+        pathfinder: &mut Pathfinder,
+        solid: &FxHashSet<BlockPos>,
+        mobility: &Mobility,
+    ) -> PathStatus {
+        pathfinder.new_frame();
+        pathfinder.find_path(
+            &BlockPos::new(0, 0, 0),
+            &BlockPos::new(9, 0, 0),
+            mobility,
+            |pos| solid.contains(pos),
+        )
+    }
+
+    #[test]
+    fn test_step_up_and_fall() {
+        let mut pathfinder = Pathfinder::default();
+        let walker = Mobility::default();
+
+        // Up & over a one block wall, dropping back down the other side
+        let solid = world(1);
+        let PathStatus::Found(path) = find(&mut pathfinder, &solid, &walker) else {
+            panic!("No path over a low wall");
+        };
+        assert_eq!(path.len(), 9);
+        assert!(path.contains(&BlockPos::new(5, 1, 0)));
+        assert_eq!(path.last(), Some(&BlockPos::new(9, 0, 0)));
+
+        // Too high to climb, but flyers go over
+        let solid = world(3);
+        assert_eq!(
+            find(&mut Pathfinder::default(), &solid, &walker),
+            PathStatus::NoPath
+        );
+        let flyer = Mobility {
+            flying: true,
+            ..walker
+        };
+        assert!(matches!(
+            find(&mut Pathfinder::default(), &solid, &flyer),
+            PathStatus::Found(_)
+        ));
+    }
+
+    #[test]
+    fn test_budget_and_cache() {
+        let solid = world(1);
+        let walker = Mobility::default();
+        let mut pathfinder = Pathfinder {
+            budget: 3,
+            ..Default::default()
+        };
+
+        // Spread over frames until it gets there
+        assert_eq!(find(&mut pathfinder, &solid, &walker), PathStatus::Pending);
+        let found = (0..10)
+            .map(|_| find(&mut pathfinder, &solid, &walker))
+            .find(|status| *status != PathStatus::Pending);
+        assert!(matches!(found, Some(PathStatus::Found(_))));
+        assert_eq!(pathfinder.cached(), 1);
+
+        // Changes off the path leave it be, ones along it mean finding it again
+        let changed = |x, y| {
+            Message::BlockChanged(BlockChangedMessage {
+                pos: BlockPos::new(x, y, 0),
+                prev_block: BlockType::Air,
+                new_block: BlockType::Stone,
+            })
+        };
+        pathfinder.handle_message(&changed(3, 5));
+        assert_eq!(pathfinder.cached(), 1);
+        pathfinder.handle_message(&changed(3, 1));
+        assert_eq!(pathfinder.cached(), 0);
+
+        // So do ones over the head of a climb onto the wall
+        let found = (0..10)
+            .map(|_| find(&mut pathfinder, &solid, &walker))
+            .find(|status| *status != PathStatus::Pending);
+        assert!(matches!(found, Some(PathStatus::Found(_))));
+        pathfinder.handle_message(&changed(4, 2));
+        assert_eq!(pathfinder.cached(), 0);
+    }
+
+    #[test]
+    fn test_forgetting() {
+        let walker = Mobility::default();
+
+        // Searches which aren't asked for again are dropped
+        let mut pathfinder = Pathfinder {
+            budget: 3,
+            ..Default::default()
+        };
+        assert_eq!(
+            find(&mut pathfinder, &world(1), &walker),
+            PathStatus::Pending
+        );
+        pathfinder.new_frame();
+        pathfinder.new_frame();
+        assert!(pathfinder.searches.is_empty());
+
+        // Only the most recently asked for paths are kept
+        let mut pathfinder = Pathfinder {
+            max_cached: 1,
+            ..Default::default()
+        };
+        let solid = world(1);
+        find(&mut pathfinder, &solid, &walker);
+        pathfinder.new_frame();
+        let shorter = pathfinder.find_path(
+            &BlockPos::new(0, 0, 0),
+            &BlockPos::new(8, 0, 0),
+            &walker,
+            |pos| solid.contains(pos),
+        );
+        assert!(matches!(shorter, PathStatus::Found(_)));
+        assert_eq!(pathfinder.cached(), 1);
+        assert!(pathfinder.paths.keys().all(|(_, goal, _)| goal.0.x == 8));
+
+        // Unreachable goals aren't searched for again until a while later
+        let solid = world(3);
+        let mut pathfinder = Pathfinder::default();
+        assert_eq!(find(&mut pathfinder, &solid, &walker), PathStatus::NoPath);
+        assert_eq!(find(&mut pathfinder, &solid, &walker), PathStatus::NoPath);
+        assert_eq!(pathfinder.spent, 0);
+        (0..pathfinder.retry_frames).for_each(|_| pathfinder.new_frame());
+        find(&mut pathfinder, &solid, &walker);
+        assert!(pathfinder.spent > 0);
+    }
+}
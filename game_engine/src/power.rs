@@ -0,0 +1,169 @@
+/*
+Crafting juice is made by generators & shared over networks of touching blocks, joined up by conduits.
+Networks only change when blocks do, so they're cached until something is placed or broken.
+*/
+
+use std::marker::PhantomData;
+
+use hecs::{Component, Entity, World};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{container::Side, entity::components::PowerNode, state::world::BlockPos};
+
+/// How a network did on its last tick
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NetworkStatus {
+    /// Number of blocks in the network
+    pub blocks: usize,
+    /// Juice per second the generators can make
+    pub supply: f32,
+    /// Juice per second the consumers want
+    pub demand: f32,
+}
+
+impl NetworkStatus {
+    /// Fraction of demand which is being met
+    pub fn satisfaction(&self) -> f32 {
+        if self.demand <= 0. {
+            1.
+        } else {
+            (self.supply / self.demand).min(1.)
+        }
+    }
+
+    /// Fraction of supply which is being used
+    pub fn load(&self) -> f32 {
+        if self.supply <= 0. {
+            0.
+        } else {
+            (self.demand / self.supply).min(1.)
+        }
+    }
+}
+
+/// Networks of touching blocks which all have some node component
+#[derive(Debug)]
+pub struct BlockNetworks<N> {
+    networks: Vec<Vec<Entity>>,
+    dirty: bool,
+    node: PhantomData<N>,
+}
+
+impl<N> Default for BlockNetworks<N> {
+    fn default() -> Self {
+        Self {
+            networks: vec![],
+            dirty: true,
+            node: PhantomData,
+        }
+    }
+}
+
+impl<N: Component> BlockNetworks<N> {
+    /// Rebuild the networks on next use, eg. when a block is placed or broken
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Whether the networks will be rebuilt on next use
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Entities in each network, rebuilding them if anything changed
+    pub fn networks(&mut self, ecs: &World) -> &[Vec<Entity>] {
+        if self.dirty {
+            let nodes = ecs
+                .query::<(&BlockPos, &N)>()
+                .iter()
+                .map(|(entity, (pos, _))| (pos.clone(), entity))
+                .collect::<FxHashMap<_, _>>();
+
+            let positions = nodes.keys().cloned().collect();
+            self.networks = flood_fill(&positions)
+                .into_iter()
+                .map(|network| network.iter().map(|pos| nodes[pos]).collect())
+                .collect();
+            self.dirty = false;
+        }
+
+        &self.networks
+    }
+}
+
+/// Every power network in the world
+pub type PowerGrid = BlockNetworks<PowerNode>;
+
+/// Group positions into sets which are joined by touching faces
+pub fn flood_fill(nodes: &FxHashSet<BlockPos>) -> Vec<Vec<BlockPos>> {
+    let mut visited = FxHashSet::default();
+    let mut groups = vec![];
+
+    for start in nodes {
+        if !visited.insert(start.clone()) {
+            continue;
+        }
+
+        let mut group = vec![];
+        let mut frontier = vec![start.clone()];
+        while let Some(pos) = frontier.pop() {
+            for side in Side::ALL {
+                let neighbour = &pos + side.offset();
+                if nodes.contains(&neighbour) && visited.insert(neighbour.clone()) {
+                    frontier.push(neighbour);
+                }
+            }
+            group.push(pos);
+        }
+        groups.push(group);
+    }
+
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_hash::FxHashSet;
+
+    use crate::{
+        power::{NetworkStatus, flood_fill},
+        state::world::BlockPos,
+    };
+
+    #[test]
+    fn test_flood_fill() {
+        // An L of conduits, plus one off on its own diagonally
+        let nodes = FxHashSet::from_iter([
+            BlockPos::new(0, 0, 0),
+            BlockPos::new(1, 0, 0),
+            BlockPos::new(2, 0, 0),
+            BlockPos::new(2, 0, 1),
+            BlockPos::new(3, 1, 2),
+        ]);
+
+        let mut sizes = flood_fill(&nodes)
+            .iter()
+            .map(|group| group.len())
+            .collect::<Vec<_>>();
+        sizes.sort();
+        assert_eq!(sizes, vec![1, 4]);
+    }
+
+    #[test]
+    fn test_throttling() {
+        let status = NetworkStatus {
+            blocks: 3,
+            supply: 1.,
+            demand: 4.,
+        };
+        assert_eq!(status.satisfaction(), 0.25);
+        assert_eq!(status.load(), 1.);
+
+        let idle = NetworkStatus {
+            supply: 2.,
+            ..Default::default()
+        };
+        assert_eq!(idle.satisfaction(), 1.);
+        assert_eq!(idle.load(), 0.);
+    }
+}
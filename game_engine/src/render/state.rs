@@ -1,7 +1,8 @@
 use std::{path::Path, sync::Arc};
 
 use anyhow::Context;
-use cgmath::{EuclideanSpace, Matrix3, Matrix4, One};
+use cgmath::{Array, EuclideanSpace, Matrix3, Matrix4, One, Vector3};
+use enum_map::EnumMap;
 use wgpu::{
     BindGroup, BindGroupDescriptor, BindGroupEntry, BindingResource, Buffer, BufferDescriptor,
     BufferUsages, CommandEncoderDescriptor, Device, LoadOp, Operations, RenderPassColorAttachment,
@@ -14,8 +15,13 @@ use super::shaders::texture;
 use crate::{
     InteractionMode,
     block::Block,
-    data::loader::{BLOCK_TEXTURES, BLOCKS, init_block_info, init_item_info},
-    entity::components::{self, Vision},
+    data::{
+        entity::EntityType,
+        loader::{
+            BLOCK_TEXTURES, BLOCKS, ITEMS, init_block_info, init_item_info, load_entity_models,
+        },
+    },
+    entity::components::{self, Conveyor, ItemEntity, Mining, Vision},
     event::{Message, Subscriber},
     render::{
         camera::{Camera, CameraUniform},
@@ -47,6 +53,61 @@ pub fn create_instance_buffer<T>(device: &Device, max_elements: usize, name: &st
     })
 }
 
+/// Most entities of each type drawn at once
+const MAX_RENDERED_ENTITIES: usize = 256;
+
+/// Most item entities drawn at once
+const MAX_RENDERED_ITEMS: usize = 4096;
+
+/// Number of crack stages shown while a block is being mined
+const CRACK_STAGES: usize = 4;
+
+/// Cracks drawn on each face of a block being mined, as lines spreading out from the middle in
+/// face coordinates. Each crack stage adds another two branches.
+const CRACK_BRANCHES: [[[f32; 2]; 3]; CRACK_STAGES * 2] = [
+    [[0.5, 0.5], [0.62, 0.58], [0.85, 0.55]],
+    [[0.5, 0.5], [0.42, 0.35], [0.38, 0.12]],
+    [[0.5, 0.5], [0.35, 0.6], [0.15, 0.7]],
+    [[0.5, 0.5], [0.58, 0.72], [0.55, 0.9]],
+    [[0.62, 0.58], [0.75, 0.78], [0.92, 0.86]],
+    [[0.42, 0.35], [0.22, 0.3], [0.08, 0.16]],
+    [[0.35, 0.6], [0.28, 0.82], [0.2, 0.95]],
+    [[0.42, 0.35], [0.68, 0.3], [0.9, 0.2]],
+];
+
+/// Create the line mesh for a crack stage, covering every face of a unit block
+fn create_crack_mesh(device: &Device, stage: usize) -> Mesh {
+    // Sit just above the block faces so the lines aren't hidden
+    let offset = 0.002;
+
+    let mut vertices: Vec<[f32; 3]> = vec![];
+    let mut indices: Vec<u32> = vec![];
+    (0..3).for_each(|axis| {
+        [-offset, 1. + offset].into_iter().for_each(|depth| {
+            CRACK_BRANCHES[..(stage + 1) * 2]
+                .iter()
+                .flat_map(|branch| branch.array_windows::<2>())
+                .flatten()
+                .for_each(|[u, v]| {
+                    let mut vertex = [0.; 3];
+                    vertex[axis] = depth;
+                    vertex[(axis + 1) % 3] = *u;
+                    vertex[(axis + 2) % 3] = *v;
+
+                    indices.push(vertices.len() as u32);
+                    vertices.push(vertex);
+                });
+        });
+    });
+
+    Mesh::new(
+        device,
+        &vertices,
+        &indices,
+        &format!("Block Crack Stage {stage}"),
+    )
+}
+
 /// Holds all of the stuff related to rendering the game window.
 pub struct RenderState {
     // GPU and window stuff
@@ -60,11 +121,14 @@ pub struct RenderState {
     // Instance buffers
     block_textured_instance_buffer: Buffer,
     block_wireframe_instance_buffer: Buffer,
-    sibeal_instance_buffer: Buffer,
+    block_crack_instance_buffer: Buffer,
+    entity_instance_buffers: EnumMap<EntityType, Buffer>,
+    item_instance_buffer: Buffer,
     // Entity stuff
     block_model: Model,
-    sibeal_model: Model,
+    entity_models: EnumMap<EntityType, Model>,
     block_wireframe_mesh: Mesh,
+    block_crack_meshes: Vec<Mesh>,
     // Camera stuff
     camera_uniform: CameraUniform,
     camera_buffer: Buffer,
@@ -76,6 +140,8 @@ pub struct RenderState {
     pub ui: UI,
     // Re-usable CPU buffers
     instances_cpu: Vec<texture::Instance>,
+    item_instances_cpu: Vec<texture::Instance>,
+    entity_instances_cpu: EnumMap<EntityType, Vec<texture::Instance>>,
     visible_blocks: Vec<Block>,
 }
 
@@ -105,15 +171,7 @@ impl RenderState {
         )
         .with_context(|| format!("Failed to load block model: {block_path:?}"))
         .unwrap();
-        let sibeal_path = Path::new("res/meshes/sibeal.obj");
-        let sibeal_model = Model::load_model(
-            sibeal_path,
-            &draw_context.device,
-            &draw_context.queue,
-            &texture_shader.texture_layout,
-        )
-        .with_context(|| format!("Failed to load sibeal model: {block_path:?}"))
-        .unwrap();
+        let entity_models = load_entity_models(&draw_context, &texture_shader.texture_layout);
 
         // Camera
         let (camera_uniform, camera_buffer) = RenderState::init_camera(&draw_context.device);
@@ -177,6 +235,11 @@ impl RenderState {
             1,
             "Block Wireframe",
         );
+        let block_crack_meshes = (0..CRACK_STAGES)
+            .map(|stage| create_crack_mesh(&draw_context.device, stage))
+            .collect();
+        let block_crack_instance_buffer =
+            create_instance_buffer::<wireframe::Instance>(&draw_context.device, 1, "Block Crack");
 
         // Instances of blocks
         let block_textured_instance_buffer = create_instance_buffer::<texture::Instance>(
@@ -184,8 +247,18 @@ impl RenderState {
             Chunk::BLOCKS_PER_CHUNK * 512,
             "Block Textured",
         );
-        let sibeal_instance_buffer =
-            create_instance_buffer::<texture::Instance>(&draw_context.device, 1, "Sibeal");
+        let entity_instance_buffers = EnumMap::from_fn(|entity_type: EntityType| {
+            create_instance_buffer::<texture::Instance>(
+                &draw_context.device,
+                MAX_RENDERED_ENTITIES,
+                &format!("{entity_type:?}"),
+            )
+        });
+        let item_instance_buffer = create_instance_buffer::<texture::Instance>(
+            &draw_context.device,
+            MAX_RENDERED_ITEMS,
+            "Item",
+        );
 
         // GUI
         let mut ui = UI::new(&draw_context.device, &draw_context.window);
@@ -214,21 +287,26 @@ impl RenderState {
             draw_context,
             texture_shader_pipeline,
             block_model,
-            sibeal_model,
+            entity_models,
             camera_uniform,
             camera_buffer,
             block_textured_instance_buffer,
-            sibeal_instance_buffer,
+            entity_instance_buffers,
+            item_instance_buffer,
             depth_texture,
             _lighting_uniform: lighting_uniform,
             _lighting_buffer: lighting_buffer,
             lighting_shader_pipeline,
             ui,
             instances_cpu: vec![],
+            item_instances_cpu: vec![],
+            entity_instances_cpu: EnumMap::default(),
             visible_blocks: vec![],
             wireframe_pipeline,
             block_wireframe_instance_buffer,
             block_wireframe_mesh,
+            block_crack_instance_buffer,
+            block_crack_meshes,
             block_texture_bind_group,
             camera: Camera::default(),
         }
@@ -294,6 +372,7 @@ impl RenderState {
             .unwrap();
         let (player_pos, vision_distance) = query.get().unwrap();
         let player_target_block = game.get_player_target_block();
+        let mining = game.ecs.get::<&Mining>(game.player).unwrap();
 
         // Check what blocks are candidates for rendering
         let (player_chunk, _) = player_pos.to_block_pos().to_chunk_offset();
@@ -356,22 +435,62 @@ impl RenderState {
 
         // Entities
 
-        let mut sibeal_query = game
-            .ecs
-            .query::<(&components::Position, &components::Orientation)>();
-        let (_, (sibeal_pos, sibeal_rot)) = sibeal_query.iter().next().unwrap();
-
-        let sibeal_instances = [texture::Instance {
-            model: (Matrix4::from_translation(sibeal_pos.0.0.to_vec())
-                * Matrix4::from(sibeal_rot.0))
-            .into(),
-            normal: Matrix3::one().into(),
-            texture_index: 0,
-        }];
+        // Grouped by type, since each is drawn with its own model
+        self.entity_instances_cpu
+            .values_mut()
+            .for_each(|instances| instances.clear());
+        game.ecs
+            .query::<(&components::Position, &components::Orientation, &EntityType)>()
+            .iter()
+            .for_each(|(_, (pos, rot, entity_type))| {
+                let instances = &mut self.entity_instances_cpu[*entity_type];
+                if instances.len() < MAX_RENDERED_ENTITIES {
+                    instances.push(texture::Instance {
+                        model: (Matrix4::from_translation(pos.0.0.to_vec()) * Matrix4::from(rot.0))
+                            .into(),
+                        normal: Matrix3::one().into(),
+                        texture_index: 0,
+                    });
+                }
+            });
+        self.entity_instances_cpu
+            .iter()
+            .for_each(|(entity_type, instances)| {
+                self.draw_context.queue.write_buffer(
+                    &self.entity_instance_buffers[entity_type],
+                    0,
+                    bytemuck::cast_slice(instances),
+                );
+            });
+
+        // Items are little blocks, those without a block of their own are covered in their icon
+        let blocks = BLOCKS.get().unwrap();
+        let items = ITEMS.get().unwrap();
+        self.item_instances_cpu.clear();
+        game.ecs
+            .query::<(&components::Position, &ItemEntity)>()
+            .iter()
+            .take(MAX_RENDERED_ITEMS)
+            .map(|(_, (pos, item))| {
+                let item = &items[item.0.item_type()];
+                let texture_index = item
+                    .data
+                    .block
+                    .map_or(item.texture_index, |block| blocks[block].texture_index);
+                let corner = pos.0.0.to_vec() - Vector3::from_value(Conveyor::ITEM_SIZE / 2.);
+                texture::Instance {
+                    model: (Matrix4::from_translation(corner)
+                        * Matrix4::from_scale(Conveyor::ITEM_SIZE))
+                    .into(),
+                    texture_index,
+                    normal: Matrix3::one().into(),
+                }
+            })
+            .collect_into(&mut self.item_instances_cpu);
         self.draw_context.queue.write_buffer(
-            &self.sibeal_instance_buffer,
+            &self.item_instance_buffer,
             0,
-            bytemuck::cast_slice(&sibeal_instances),
+            bytemuck::cast_slice(&self.item_instances_cpu),
         );
 
         // Get a view on the surface texture that we'll draw to
@@ -423,17 +542,30 @@ impl RenderState {
                 self.instances_cpu.len(),
             );
 
-            // Draw Sibeal
-            let mesh = &self.sibeal_model.meshes[0];
-            let material = &self.sibeal_model.materials[0];
+            // Draw items lying around
+            let mesh = &self.block_model.meshes[0];
             self.texture_shader_pipeline.draw(
                 &mut render_pass,
                 mesh,
-                &material.bind_group,
-                &self.sibeal_instance_buffer,
-                1,
+                &self.block_texture_bind_group,
+                &self.item_instance_buffer,
+                self.item_instances_cpu.len(),
             );
 
+            // Draw entities
+            self.entity_models
+                .iter()
+                .filter(|(entity_type, _)| !self.entity_instances_cpu[*entity_type].is_empty())
+                .for_each(|(entity_type, model)| {
+                    self.texture_shader_pipeline.draw(
+                        &mut render_pass,
+                        &model.meshes[0],
+                        &model.materials[0].bind_group,
+                        &self.entity_instance_buffers[entity_type],
+                        self.entity_instances_cpu[entity_type].len(),
+                    );
+                });
+
             // Draw the light object
             let mesh = &self.block_model.meshes[0];
             self.lighting_shader_pipeline
@@ -458,6 +590,28 @@ impl RenderState {
                     &self.block_wireframe_instance_buffer,
                     1,
                 );
+
+                // Show how close the block is to breaking
+                if mining.target.as_ref() == Some(&block.block_pos) && mining.progress > 0. {
+                    let stage =
+                        ((mining.progress * CRACK_STAGES as f32) as usize).min(CRACK_STAGES - 1);
+                    let instance = wireframe::Instance {
+                        color: [0.1, 0.1, 0.1],
+                        ..instance
+                    };
+                    self.draw_context.queue.write_buffer(
+                        &self.block_crack_instance_buffer,
+                        0,
+                        bytemuck::cast_slice(&[instance]),
+                    );
+
+                    self.wireframe_pipeline.draw(
+                        &mut render_pass,
+                        &self.block_crack_meshes[stage],
+                        &self.block_crack_instance_buffer,
+                        1,
+                    );
+                }
             }
         }
 
@@ -475,6 +629,7 @@ impl RenderState {
                 // format!("player: {:#?}", game.player.aabb()),
                 format!("Blocks rendered: {}", self.instances_cpu.len()),
                 format!("Target block: {player_target_block:?}"),
+                format!("Mining progress: {:.2}", mining.progress),
             ])
             .for_each(|l| DEBUG_WINDOW.add_line(&l));
 
@@ -500,6 +655,8 @@ impl RenderState {
 
 impl Subscriber for RenderState {
     fn handle_message(&mut self, event: &Message) {
+        self.ui.handle_message(event);
+
         use Message::*;
         match event {
             PlayerMoved((pos, orientation)) => {
@@ -1,32 +1,47 @@
 use std::time::Duration;
 
-use cgmath::{Deg, InnerSpace, MetricSpace, Quaternion, Rotation3, Vector3};
+use cgmath::{InnerSpace, MetricSpace, Vector3};
 use hecs::Entity;
 use itertools::Itertools;
+use rustc_hash::FxHashSet;
 use winit::event::{ElementState, KeyEvent, MouseButton, WindowEvent};
 
 use crate::{
     InteractionMode,
+    behaviour::behaviour_tick,
     block::Block,
+    container::Side,
     data::{
         block::BlockType,
+        entity::EntityType,
         loader::{BLOCKS, ITEMS},
     },
     entity::{
-        components::{self, Container, Crafter, EntityType, Reach, UprightOrientation, Vision},
+        components::{
+            ChunkLoader, Conveyor, Crafter, CrafterUpdate, Hopper, Inventory, ItemFilter, Mining,
+            Progression, Reach, UprightOrientation, Vision,
+        },
         systems::{
-            MoveSystem, System, crafting_tick, create_block_state, get_breaking_strength,
-            get_held_item, transfer_item,
+            block_tick, conveyor_tick, create_block_state, empty_conveyor, get_held_item,
+            get_mining_capability, hopper_tick, power_tick, progression_tick, sorting_tick,
+            spawn_entity, transfer_item, wear_held_item,
         },
     },
     event::{
         MESSAGE_QUEUE, Message, Subscriber,
         messages::{
-            BlockChangedMessage, PlaceBlockMessage, SetCraftingRecipeMessage, SpawnEntityMessage,
+            BlockChangedMessage, PlaceBlockMessage, SetConveyorDirectionMessage,
+            SetHopperConfigMessage, SetItemFilterMessage, SpawnEntityMessage,
+            TransferFailedMessage, UpdateCrafterMessage,
         },
     },
     math::ray::{Ray, RayCollision},
-    state::world::{Chunk, World, WorldPos},
+    pathfinding::Pathfinder,
+    power::PowerGrid,
+    state::world::{Chunk, ChunkPos, World, WorldPos},
+    storage::{StorageGrid, update_terminals},
+    tick::{TickScheduler, wake},
+    ui::debug::DEBUG_WINDOW,
 };
 
 /// Holds state information about the game independent of the rendering
@@ -35,6 +50,12 @@ pub struct GameState {
     pub world: World,
     pub entities: Vec<Entity>,
     pub ecs: hecs::World,
+    pub power: PowerGrid,
+    pub ticks: TickScheduler,
+    pub storage: StorageGrid,
+    pub paths: Pathfinder,
+    /// Chunks where block entities run, near the player or kept loaded by chunk loaders
+    pub active_chunks: FxHashSet<ChunkPos>,
 }
 
 impl GameState {
@@ -53,14 +74,53 @@ impl GameState {
 
     /// Update the world by a game tick
     pub fn tick(&mut self, duration: &Duration) {
+        self.paths.new_frame();
         self.generate_chunks();
+        self.update_active_chunks();
+        self.mine_block(duration);
 
         self.run_ecs_systems(duration);
     }
 
     pub fn run_ecs_systems(&mut self, duration: &Duration) {
-        MoveSystem::tick(&mut self.ecs, duration);
-        crafting_tick(&mut self.ecs, duration);
+        behaviour_tick(
+            &mut self.ecs,
+            &self.world,
+            &mut self.paths,
+            self.player,
+            duration,
+        );
+        power_tick(&mut self.ecs, &mut self.power, &self.active_chunks);
+        block_tick(
+            &mut self.ecs,
+            &mut self.ticks,
+            &self.active_chunks,
+            duration,
+        );
+        hopper_tick(
+            &self.ecs,
+            &self.world.block_states,
+            &self.active_chunks,
+            duration,
+        )
+        .into_iter()
+        .for_each(|m| MESSAGE_QUEUE.send(Message::TransferItem(m)));
+        sorting_tick(
+            &self.ecs,
+            &self.world.block_states,
+            &self.active_chunks,
+            duration,
+        )
+        .into_iter()
+        .for_each(|m| MESSAGE_QUEUE.send(Message::TransferItem(m)));
+        conveyor_tick(
+            &mut self.ecs,
+            &self.world.block_states,
+            &self.active_chunks,
+            duration,
+        );
+        update_terminals(&mut self.ecs, &mut self.storage);
+        progression_tick(&mut self.ecs);
     }
 
     pub fn handle_keypress(&mut self, _event: &KeyEvent) {}
@@ -75,15 +135,18 @@ impl GameState {
             }
         }
 
-        if matches!(
-            event,
-            WindowEvent::MouseInput {
-                state: ElementState::Pressed,
-                button: MouseButton::Left,
-                ..
+        if let WindowEvent::MouseInput {
+            state,
+            button: MouseButton::Left,
+            ..
+        } = event
+        {
+            // Mining happens over time while the button is held
+            let mut mining = self.ecs.get::<&mut Mining>(self.player).unwrap();
+            mining.active = state.is_pressed();
+            if !mining.active {
+                mining.reset();
             }
-        ) {
-            self.break_block();
         } else if matches!(
             event,
             WindowEvent::MouseInput {
@@ -143,6 +206,31 @@ impl GameState {
         });
     }
 
+    /// Find which chunks block entities run in: those the player can see, plus any kept loaded
+    fn update_active_chunks(&mut self) {
+        let mut query = self
+            .ecs
+            .query_one::<(&WorldPos, &Vision)>(self.player)
+            .unwrap();
+        let (player_pos, vision_distance) = query.get().unwrap();
+
+        let (player_chunk, _) = player_pos.to_block_pos().to_chunk_offset();
+        let player_vision_chunks = (vision_distance.0 as u32).div_ceil(Chunk::CHUNK_SIZE as u32);
+
+        let forced = self.world.forced_chunks();
+        DEBUG_WINDOW.add_line(&format!(
+            "Forced chunks ({}/{}): {:?}",
+            forced.len(),
+            self.world.max_forced_chunks,
+            forced.iter().map(|pos| pos.0).collect::<Vec<_>>()
+        ));
+
+        self.active_chunks = player_chunk
+            .chunks_within(player_vision_chunks)
+            .chain(forced)
+            .collect();
+    }
+
     /// Get the block that the player is looking at
     pub fn get_player_target_block(&self) -> Option<Block> {
         self.get_player_target_block_verbose()
@@ -212,32 +300,88 @@ impl GameState {
             .next()
     }
 
-    /// Attempt to break the block the player is targeting
-    fn break_block(&mut self) {
-        // Break action needs a target
-        let Some(target_block) = self.get_player_target_block() else {
+    /// Make progress on breaking the block the player is targeting while the mine button is held
+    fn mine_block(&mut self, duration: &Duration) {
+        let target_block = self.get_player_target_block();
+        let capability = get_mining_capability(&self.ecs, self.player);
+        let in_game = matches!(
+            *self.ecs.get::<&InteractionMode>(self.player).unwrap(),
+            InteractionMode::Game
+        );
+
+        let mut mining = self.ecs.get::<&mut Mining>(self.player).unwrap();
+
+        // Opening a UI lets go of the button
+        if !in_game {
+            mining.active = false;
+        }
+
+        // Mining needs a target
+        let Some(target_block) = target_block.filter(|_| mining.active) else {
+            mining.reset();
             return;
         };
 
+        // Looking at a different block starts over
+        if mining.target.as_ref() != Some(&target_block.block_pos) {
+            mining.target = Some(target_block.block_pos.clone());
+            mining.progress = 0.;
+        }
+
         let blocks = BLOCKS.get().unwrap();
+        let Some(block_mining) = capability.mine(&blocks[target_block.block_type].data) else {
+            // Block is too hard
+            mining.progress = 0.;
+            return;
+        };
 
-        // Check if player can break the block
-        if blocks[target_block.block_type]
+        mining.progress += duration.as_secs_f32() / block_mining.break_time;
+        if mining.progress < 1. {
+            return;
+        }
+
+        // A full inventory can't take the block's item, so the block holds out
+        let item_on_break = blocks[target_block.block_type]
             .data
-            .hardness
-            .is_none_or(|h| h > get_breaking_strength(&self.ecs, self.player))
+            .item_on_break
+            .filter(|_| block_mining.drops);
+        if let Some(item) = item_on_break
+            && self
+                .ecs
+                .get::<&Inventory>(self.player)
+                .unwrap()
+                .space_for(item)
+                == 0
         {
-            // Block is too hard
+            mining.progress = 1.;
             return;
         }
 
+        // Keep mining whatever is looked at next
+        mining.reset();
+        drop(mining);
+        self.break_block(&target_block, block_mining.drops);
+    }
+
+    /// Break a block, giving the item to the player if it drops
+    fn break_block(&mut self, target_block: &Block, drops: bool) {
+        let blocks = BLOCKS.get().unwrap();
+
         // Break block
-        MESSAGE_QUEUE.send(Message::BreakBlock(target_block.block_pos));
+        MESSAGE_QUEUE.send(Message::BreakBlock(target_block.block_pos.clone()));
 
         // Give an item to the player
-        if let Some(item) = blocks[target_block.block_type].data.item_on_break {
-            let mut inventory = self.ecs.get::<&mut Container>(self.player).unwrap();
-            inventory.add_item(item, 1);
+        if let Some(item) = blocks[target_block.block_type].data.item_on_break
+            && drops
+        {
+            let mut inventory = self.ecs.get::<&mut Inventory>(self.player).unwrap();
+            let overflow = inventory.add_item(item, 1);
+            assert_eq!(overflow, 0, "No room for block item!");
+        }
+
+        // Using a tool wears it down
+        if wear_held_item(&mut self.ecs, self.player, 1) {
+            log::info!("Held item broke!");
         }
     }
 
@@ -268,7 +412,7 @@ impl GameState {
         }
 
         // Remove the item from the player's inventory
-        let mut inventory = self.ecs.get::<&mut Container>(self.player).unwrap();
+        let mut inventory = self.ecs.get::<&mut Inventory>(self.player).unwrap();
         inventory.remove_item(item, 1);
 
         // Place the block
@@ -300,14 +444,18 @@ impl Subscriber for GameState {
     fn handle_message(&mut self, event: &Message) {
         match event {
             Message::TransferItem(m) => {
-                transfer_item(&mut self.ecs, m);
+                // Stale transfers are dropped, letting whoever asked know why
+                if let Err(reason) = transfer_item(&mut self.ecs, m) {
+                    MESSAGE_QUEUE.send(Message::TransferFailed(TransferFailedMessage {
+                        source: m.source,
+                        dest: m.dest,
+                        item: m.item,
+                        reason,
+                    }));
+                }
             }
             Message::SpawnEntity(SpawnEntityMessage { pos, entity_type }) => {
-                // Create the entity
-                let entity_id = self.ecs.spawn((
-                    components::Position(*pos),
-                    components::Orientation(Quaternion::from_angle_y(Deg(180.))),
-                ));
+                let entity_id = spawn_entity(&mut self.ecs, *pos, *entity_type);
                 self.entities.push(entity_id);
             }
             Message::PlaceBlock(PlaceBlockMessage { pos, block }) => {
@@ -315,11 +463,29 @@ impl Subscriber for GameState {
 
                 // Place the block
                 *self.world.get_block_mut(pos).unwrap() = *block;
+                self.power.mark_dirty();
+                self.storage.mark_dirty();
 
                 // Create a state if the block is stateful
                 if blocks[*block].data.state.is_some() {
                     // Spawn a new entity for our block state
                     let entity = create_block_state(&mut self.ecs, pos, *block);
+
+                    // Belts run away from whoever placed them
+                    if let Ok(mut conveyor) = self.ecs.get::<&mut Conveyor>(entity) {
+                        let orientation = self
+                            .ecs
+                            .get::<&UprightOrientation>(self.player)
+                            .expect("Player has no orientation!");
+                        conveyor.direction = Side::facing(orientation.forward());
+                    }
+                    // Chunk loaders start keeping their chunks loaded straight away
+                    if let Ok(mut loader) = self.ecs.get::<&mut ChunkLoader>(entity) {
+                        loader.error = self
+                            .world
+                            .force_load(pos.clone(), ChunkLoader::chunks(pos))
+                            .err();
+                    }
                     let old_entity = self.world.block_states.insert(pos.clone(), entity);
                     assert!(
                         old_entity.is_none(),
@@ -341,6 +507,8 @@ impl Subscriber for GameState {
 
                 // Break block
                 let old_block = std::mem::replace(block_type, BlockType::Air);
+                self.power.mark_dirty();
+                self.storage.mark_dirty();
 
                 // Remove the block state if it was stateful
                 if blocks[old_block].data.state.is_some() {
@@ -348,6 +516,25 @@ impl Subscriber for GameState {
                     let Some(old_entity) = self.world.block_states.remove(pos) else {
                         panic!("Attempted to remove stateful block, but no state existed! {pos:?}");
                     };
+                    for loader in self.world.unforce_load(pos) {
+                        let entity = self.world.block_states[&loader];
+                        if let Ok(mut loader) = self.ecs.get::<&mut ChunkLoader>(entity) {
+                            loader.error = None;
+                        }
+                    }
+
+                    // Items on a belt would be stranded without it
+                    if self.ecs.satisfies::<&Conveyor>(old_entity).unwrap_or(false) {
+                        let lost = empty_conveyor(
+                            &mut self.ecs,
+                            &self.world.block_states,
+                            old_entity,
+                            self.player,
+                        );
+                        lost.iter().for_each(|stack| {
+                            log::warn!("No room for {stack:?} from a broken belt, lost!");
+                        });
+                    }
 
                     self.ecs
                         .despawn(old_entity)
@@ -361,19 +548,81 @@ impl Subscriber for GameState {
                     new_block: BlockType::Air,
                 }));
             }
-            Message::SetCraftingRecipe(SetCraftingRecipeMessage { block, recipe }) => {
+            Message::UpdateCrafter(UpdateCrafterMessage { block, update }) => {
                 let entity = self
                     .world
                     .block_states
                     .get(block)
                     .expect("Block state doesn't exist!");
 
-                let mut state = self
+                // Recipes have to be unlocked to automate, whatever the UI allowed
+                if let CrafterUpdate::Queue(job) = update {
+                    let progression = self
+                        .ecs
+                        .get::<&Progression>(self.player)
+                        .expect("Player has no progression!");
+                    if !progression.can_automate(&job.recipe) {
+                        log::warn!("Recipe for {:?} isn't unlocked yet", job.recipe.output().0);
+                        return;
+                    }
+                }
+
+                let mut crafter = self
                     .ecs
                     .get::<&mut Crafter>(*entity)
                     .expect("Entity for block state doesn't exist!");
 
-                state.recipe = Some(recipe.clone());
+                crafter.update(update);
+                wake(&self.ecs, *entity);
+            }
+            Message::SetHopperConfig(SetHopperConfigMessage {
+                block,
+                from,
+                to,
+                filter,
+            }) => {
+                let entity = self
+                    .world
+                    .block_states
+                    .get(block)
+                    .expect("Block state doesn't exist!");
+
+                let mut hopper = self
+                    .ecs
+                    .get::<&mut Hopper>(*entity)
+                    .expect("Entity for block state doesn't exist!");
+
+                hopper.from = *from;
+                hopper.to = *to;
+                hopper.filter = filter.clone();
+            }
+            Message::SetItemFilter(SetItemFilterMessage { block, filter }) => {
+                let entity = self
+                    .world
+                    .block_states
+                    .get(block)
+                    .expect("Block state doesn't exist!");
+
+                let mut item_filter = self
+                    .ecs
+                    .get::<&mut ItemFilter>(*entity)
+                    .expect("Entity for block state doesn't exist!");
+
+                *item_filter = filter.clone();
+            }
+            Message::SetConveyorDirection(SetConveyorDirectionMessage { block, direction }) => {
+                let entity = self
+                    .world
+                    .block_states
+                    .get(block)
+                    .expect("Block state doesn't exist!");
+
+                let mut conveyor = self
+                    .ecs
+                    .get::<&mut Conveyor>(*entity)
+                    .expect("Entity for block state doesn't exist!");
+
+                conveyor.direction = *direction;
             }
             _ => (),
         }
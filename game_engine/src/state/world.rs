@@ -1,8 +1,8 @@
-use std::ops;
+use std::{fmt::Display, ops};
 
 use cgmath::{InnerSpace, Point3, Vector3};
 use num_traits::Euclid;
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 
 use crate::{
     block::Block,
@@ -221,15 +221,105 @@ impl<'a> Iterator for ChunkIter<'a> {
     }
 }
 
+/// Why a chunk loader can't keep its chunks loaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForceLoadError {
+    /// Loading them would go over the limit
+    TooManyChunks(usize),
+}
+
+impl Display for ForceLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ForceLoadError::TooManyChunks(max) => {
+                write!(f, "Too many chunks loaded, the most is {max}")
+            }
+        }
+    }
+}
+
 /// All of the world data
 pub struct World {
     // Generated chunks
     pub chunks: FxHashMap<ChunkPos, Chunk>,
     pub generator: Box<dyn ChunkGenerator>,
     pub block_states: FxHashMap<BlockPos, hecs::Entity>,
+    /// Chunks kept loaded wherever the player is, by the chunk loader keeping them
+    pub chunk_loaders: FxHashMap<BlockPos, Vec<ChunkPos>>,
+    /// Most chunks which can be kept loaded by chunk loaders at once
+    pub max_forced_chunks: usize,
+    /// Chunk loaders turned away for going over the limit, tried again when one is removed
+    pub pending_loaders: Vec<(BlockPos, Vec<ChunkPos>)>,
 }
 
 impl World {
+    /// Most chunks chunk loaders can keep loaded at once, unless the world says otherwise
+    pub const DEFAULT_MAX_FORCED_CHUNKS: usize = 64;
+
+    /// Empty world, generating chunks as they're needed
+    pub fn new(generator: Box<dyn ChunkGenerator>, max_forced_chunks: usize) -> Self {
+        Self {
+            chunks: Default::default(),
+            generator,
+            block_states: Default::default(),
+            chunk_loaders: Default::default(),
+            max_forced_chunks,
+            pending_loaders: vec![],
+        }
+    }
+
+    /// Every chunk being kept loaded by a chunk loader
+    pub fn forced_chunks(&self) -> FxHashSet<ChunkPos> {
+        self.chunk_loaders.values().flatten().cloned().collect()
+    }
+
+    /// Keep some chunks loaded for a chunk loader, as long as it doesn't go over the limit.
+    /// Chunks shared with other loaders only count once. Loaders turned away wait to be retried.
+    pub fn force_load(
+        &mut self,
+        loader: BlockPos,
+        chunks: Vec<ChunkPos>,
+    ) -> Result<(), ForceLoadError> {
+        self.try_force_load(loader.clone(), chunks.clone())
+            .inspect_err(|_| self.pending_loaders.push((loader, chunks)))
+    }
+
+    fn try_force_load(
+        &mut self,
+        loader: BlockPos,
+        chunks: Vec<ChunkPos>,
+    ) -> Result<(), ForceLoadError> {
+        let mut forced = self.forced_chunks();
+        forced.extend(chunks.iter().cloned());
+        if forced.len() > self.max_forced_chunks {
+            return Err(ForceLoadError::TooManyChunks(self.max_forced_chunks));
+        }
+
+        chunks.iter().for_each(|pos| {
+            self.get_or_generate_chunk(pos);
+        });
+        self.chunk_loaders.insert(loader, chunks);
+        Ok(())
+    }
+
+    /// Stop keeping a chunk loader's chunks loaded, making room for any waiting loaders.
+    /// Returns the waiting loaders which now keep their chunks loaded.
+    pub fn unforce_load(&mut self, loader: &BlockPos) -> Vec<BlockPos> {
+        self.chunk_loaders.remove(loader);
+        self.pending_loaders.retain(|(pos, _)| pos != loader);
+
+        let pending = std::mem::take(&mut self.pending_loaders);
+        let mut loaded = vec![];
+        for (pos, chunks) in pending {
+            match self.try_force_load(pos.clone(), chunks.clone()) {
+                Ok(()) => loaded.push(pos),
+                Err(_) => self.pending_loaders.push((pos, chunks)),
+            }
+        }
+
+        loaded
+    }
+
     fn update_all_exposed_blocks(&mut self) {
         let chunks_to_update = self.chunks.keys().cloned().collect::<Vec<_>>();
         chunks_to_update
@@ -319,11 +409,7 @@ impl Default for World {
             Perlin::new(42, 1, 1., 0.5, 1. / 256.),
         );
 
-        World {
-            chunks: Default::default(),
-            generator: Box::new(chunk_gen),
-            block_states: Default::default(),
-        }
+        World::new(Box::new(chunk_gen), Self::DEFAULT_MAX_FORCED_CHUNKS)
     }
 }
 
@@ -336,3 +422,37 @@ impl Subscriber for World {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::state::world::{BlockPos, ChunkPos, ForceLoadError, World};
+
+    #[test]
+    fn test_force_load() {
+        let mut world = World {
+            max_forced_chunks: 10,
+            ..Default::default()
+        };
+        let chunks_around = |x| ChunkPos::new(x, 0, 0).chunks_within(1).collect::<Vec<_>>();
+
+        world
+            .force_load(BlockPos::new(0, 0, 0), chunks_around(0))
+            .unwrap();
+        assert!(world.chunks.contains_key(&ChunkPos::new(1, 0, 0)));
+
+        // Overlapping chunks only count once, but it's still too many
+        assert_eq!(
+            world.force_load(BlockPos::new(32, 0, 0), chunks_around(2)),
+            Err(ForceLoadError::TooManyChunks(10))
+        );
+        assert_eq!(world.forced_chunks().len(), 7);
+
+        // The loader turned away takes over once there's room
+        assert_eq!(
+            world.unforce_load(&BlockPos::new(0, 0, 0)),
+            vec![BlockPos::new(32, 0, 0)]
+        );
+        assert!(world.forced_chunks().contains(&ChunkPos::new(3, 0, 0)));
+        assert!(world.pending_loaders.is_empty());
+    }
+}
@@ -0,0 +1,235 @@
+/*
+Chests joined up to a terminal by touching each other, so all their items can be got at from one place.
+Networks are worked out the same way as power networks, with the same caching between block changes.
+*/
+
+use std::cmp::Reverse;
+
+use enum_map::EnumMap;
+use hecs::{Entity, World};
+use rustc_hash::FxHashMap;
+
+use crate::{
+    data::item::ItemType,
+    entity::{
+        components::{Inventory, ItemFilter, StorageNode, StorageTerminal},
+        systems::with_container,
+    },
+    event::messages::TransferItemMessage,
+    power::BlockNetworks,
+};
+
+/// Every storage network in the world
+#[derive(Debug, Default)]
+pub struct StorageGrid {
+    networks: BlockNetworks<StorageNode>,
+    /// What each chest held when the terminals were last totalled up
+    held: FxHashMap<Entity, (EnumMap<ItemType, usize>, usize)>,
+}
+
+impl StorageGrid {
+    /// Rebuild the networks on next use, eg. when a block is placed or broken
+    pub fn mark_dirty(&mut self) {
+        self.networks.mark_dirty();
+    }
+}
+
+/// Split a transfer to or from a terminal between the chests on its network.
+/// Items going in fill up the highest priority chests which allow them, starting with ones which
+/// already hold some. Items going out come from the lowest priority chests holding them.
+/// Anything else is passed on as it is.
+pub fn route_transfer(ecs: &World, message: TransferItemMessage) -> Vec<TransferItemMessage> {
+    let chests_of = |entity| {
+        ecs.get::<&StorageTerminal>(entity)
+            .ok()
+            .map(|terminal| terminal.chests.clone())
+    };
+
+    let (chests, inserting) = match (chests_of(message.source), chests_of(message.dest)) {
+        (Some(chests), _) => (chests, false),
+        (None, Some(chests)) => (chests, true),
+        (None, None) => return vec![message],
+    };
+
+    let mut amounts = chests
+        .iter()
+        .filter_map(|chest| {
+            let held = ecs.get::<&Inventory>(*chest).ok()?.items[message.item];
+            let priority = ecs
+                .get::<&ItemFilter>(*chest)
+                .map_or(0, |filter| filter.priority);
+            let amount = match inserting {
+                // Goes through the chest's filter
+                true => with_container(ecs, *chest, |c| c.space_for_side(message.item, None))?,
+                false => held,
+            };
+            Some((*chest, held, priority, amount))
+        })
+        .filter(|(_, _, _, amount)| *amount > 0)
+        .collect::<Vec<_>>();
+    match inserting {
+        // Keep items together rather than spreading them across every chest
+        true => amounts.sort_by_key(|(_, held, priority, _)| (Reverse(*priority), *held == 0)),
+        false => amounts.sort_by_key(|(_, _, priority, _)| *priority),
+    }
+
+    let mut remaining = message.count;
+    let routed = amounts
+        .into_iter()
+        .map_while(|(chest, _, _, amount)| {
+            let count = amount.min(remaining);
+            remaining -= count;
+            (count > 0).then_some(TransferItemMessage {
+                source: if inserting { message.source } else { chest },
+                dest: if inserting { chest } else { message.dest },
+                count,
+                ..message
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // Nowhere to route it, so it fails & gets reported like any other transfer
+    match routed.is_empty() {
+        true => vec![message],
+        false => routed,
+    }
+}
+
+/// Point each terminal at the chests on its network, and total up what they hold.
+/// Networks are only totalled up again when they're rebuilt or one of their chests changes.
+pub fn update_terminals(ecs: &mut World, grid: &mut StorageGrid) {
+    let rebuilt = grid.networks.is_dirty();
+    if rebuilt {
+        grid.held.clear();
+    }
+
+    for network in grid.networks.networks(ecs) {
+        let chests = network
+            .iter()
+            .filter(|entity| ecs.satisfies::<&Inventory>(**entity).unwrap_or(false))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let mut changed = rebuilt;
+        for chest in &chests {
+            let inventory = ecs.get::<&Inventory>(*chest).unwrap();
+            let held = (inventory.items, inventory.instances.len());
+            changed |= grid.held.insert(*chest, held) != Some(held);
+        }
+        if !changed {
+            continue;
+        }
+
+        let mut contents = Inventory::default();
+        chests.iter().for_each(|chest| {
+            let inventory = ecs.get::<&Inventory>(*chest).unwrap();
+            inventory.items.iter().for_each(|(item, count)| {
+                contents.items[item] += count;
+            });
+            contents
+                .instances
+                .extend(inventory.instances.iter().cloned());
+        });
+
+        for entity in network {
+            if let Ok(mut terminal) = ecs.get::<&mut StorageTerminal>(*entity) {
+                terminal.chests = chests.clone();
+                terminal.contents = contents.clone();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hecs::World;
+
+    use crate::{
+        data::item::ItemType,
+        entity::components::{Capacity, Inventory, StorageNode, StorageTerminal},
+        event::messages::TransferItemMessage,
+        state::world::BlockPos,
+        storage::{StorageGrid, route_transfer, update_terminals},
+    };
+
+    #[test]
+    fn test_terminal_routing() {
+        let mut ecs = World::new();
+        let chest = |stone| {
+            let mut inventory = Inventory::with_capacity(Capacity {
+                max_slots: Some(1),
+                max_stack: Some(10),
+                ..Default::default()
+            });
+            inventory.add_item(ItemType::Stone, stone);
+            inventory
+        };
+
+        // Terminal with a line of chests off it, and one chest not touching
+        let terminal = ecs.spawn((
+            BlockPos::new(0, 0, 0),
+            StorageNode,
+            StorageTerminal::default(),
+        ));
+        let empty = ecs.spawn((BlockPos::new(1, 0, 0), StorageNode, chest(0)));
+        let partial = ecs.spawn((BlockPos::new(2, 0, 0), StorageNode, chest(4)));
+        ecs.spawn((BlockPos::new(5, 0, 0), StorageNode, chest(10)));
+        let player = ecs.spawn((Inventory::default(),));
+
+        let mut grid = StorageGrid::default();
+        update_terminals(&mut ecs, &mut grid);
+        assert_eq!(
+            ecs.get::<&StorageTerminal>(terminal)
+                .unwrap()
+                .contents
+                .items[ItemType::Stone],
+            4
+        );
+
+        // Going in tops up the chest which already has some before starting on the empty one
+        let routed = route_transfer(
+            &ecs,
+            TransferItemMessage {
+                source: player,
+                dest: terminal,
+                item: ItemType::Stone,
+                count: 8,
+                source_side: None,
+                dest_side: None,
+            },
+        );
+        let routed = routed.iter().map(|m| (m.dest, m.count)).collect::<Vec<_>>();
+        assert_eq!(routed, vec![(partial, 6), (empty, 2)]);
+
+        // Going out only takes from chests which have some
+        let routed = route_transfer(
+            &ecs,
+            TransferItemMessage {
+                source: terminal,
+                dest: player,
+                item: ItemType::Stone,
+                count: 8,
+                source_side: None,
+                dest_side: None,
+            },
+        );
+        let routed = routed
+            .iter()
+            .map(|m| (m.source, m.count))
+            .collect::<Vec<_>>();
+        assert_eq!(routed, vec![(partial, 4)]);
+
+        // Picks up chests changing without the network being rebuilt
+        ecs.get::<&mut Inventory>(empty)
+            .unwrap()
+            .add_item(ItemType::Stone, 3);
+        update_terminals(&mut ecs, &mut grid);
+        assert_eq!(
+            ecs.get::<&StorageTerminal>(terminal)
+                .unwrap()
+                .contents
+                .items[ItemType::Stone],
+            7
+        );
+    }
+}
@@ -0,0 +1,398 @@
+/*
+Machines which work away in the background as game time passes, eg. crafters & furnaces.
+Each block ticks at its own rate, and goes to sleep when it's got nothing to do until its items change.
+*/
+
+use hecs::{Entity, World};
+
+use crate::entity::components::{Crafter, Furnace, Generator, Ticker};
+
+/// Whether a machine still has work to do after a tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TickState {
+    Busy,
+    /// Nothing can happen until its items change, so it can sleep
+    Idle,
+}
+
+/// Anything which does work over time
+pub trait Tickable {
+    /// Do `seconds` worth of work. Long stretches are worked out in one go rather than step by
+    /// step, but end up the same as if they'd been ticked the whole time.
+    fn tick(&mut self, seconds: f32) -> TickState;
+}
+
+/// Catching up assumes the power the crafter has when it resumes was there the whole time it was
+/// away, as nothing tracks how the network's supply changed in the meantime.
+impl Tickable for Crafter {
+    fn tick(&mut self, seconds: f32) -> TickState {
+        let rate = self.juice_per_second * self.power;
+        let mut seconds = seconds;
+        loop {
+            // Only process when there's a job, enough materials & somewhere to put the result
+            let Some((job, recipe)) = self
+                .active_job()
+                .map(|job| (job, &self.queue[job].recipe))
+                .filter(|(_, recipe)| self.can_craft(recipe))
+            else {
+                return TickState::Idle;
+            };
+            let cost = recipe.crafting_juice_cost;
+
+            // As many crafts as the juice covers, the inputs allow & the job wants
+            let juice = self.crafting_juice + rate * seconds;
+            let crafts = ((juice / cost) as usize)
+                .min(recipe.max_crafts(&self.input.items))
+                .min(self.queue[job].crafts_left(&self.output));
+            if crafts == 0 {
+                self.crafting_juice = juice;
+                return TickState::Busy;
+            }
+
+            // Catalysts & output space can still run out part way through
+            let made = (0..crafts)
+                .take_while(|_| {
+                    let craftable = self.recipe().is_some_and(|r| self.can_craft(r));
+                    if craftable {
+                        self.craft();
+                    }
+                    craftable
+                })
+                .count();
+
+            // Only the time taken to make them is used up, juice stops flowing once it's idle
+            let used = made as f32 * cost - self.crafting_juice;
+            if rate > 0. && used > 0. {
+                seconds = (seconds - used / rate).max(0.);
+                self.crafting_juice = 0.;
+            } else {
+                self.crafting_juice -= made as f32 * cost;
+            }
+        }
+    }
+}
+
+impl Tickable for Furnace {
+    fn tick(&mut self, seconds: f32) -> TickState {
+        let mut seconds = seconds;
+        loop {
+            let recipe = self.smeltable();
+
+            // Progress is lost if the ingredients change
+            if recipe.map(std::ptr::from_ref) != self.recipe.map(std::ptr::from_ref) {
+                self.cook_time = 0.;
+            }
+            self.recipe = recipe;
+            let Some(recipe) = recipe else {
+                return TickState::Idle;
+            };
+
+            // Fuel is only burnt while there's something to cook
+            let needed = recipe.cook_time - self.cook_time;
+            let burnt = self.burner.burn(seconds.min(needed));
+            seconds -= burnt;
+
+            if burnt < needed {
+                self.cook_time += burnt;
+                return match self.burner.has_fuel() {
+                    true => TickState::Busy,
+                    false => TickState::Idle,
+                };
+            }
+
+            self.cook_time = 0.;
+            recipe.inputs.iter().for_each(|(item, count)| {
+                self.input.remove_item(*item, *count);
+            });
+            let (item, count) = recipe.output;
+            self.output.add_item(item, count);
+        }
+    }
+}
+
+/// Generators burn fuel as fast as the network is using their output. Like crafters, catching up
+/// assumes the load when it resumes was there the whole time.
+impl Tickable for Generator {
+    fn tick(&mut self, seconds: f32) -> TickState {
+        let Some(burner) = &mut self.burner else {
+            // Nothing to burn, so nothing to do
+            return TickState::Idle;
+        };
+
+        burner.burn(seconds * self.load);
+        match burner.has_fuel() {
+            true => TickState::Busy,
+            false => TickState::Idle,
+        }
+    }
+}
+
+/// Run a function on whichever tickable an entity has. None if it doesn't do work over time.
+pub fn with_tickable<R>(
+    ecs: &World,
+    entity: Entity,
+    f: impl FnOnce(&mut dyn Tickable) -> R,
+) -> Option<R> {
+    if let Ok(mut crafter) = ecs.get::<&mut Crafter>(entity) {
+        return Some(f(&mut *crafter));
+    }
+    if let Ok(mut furnace) = ecs.get::<&mut Furnace>(entity) {
+        return Some(f(&mut *furnace));
+    }
+    if let Ok(mut generator) = ecs.get::<&mut Generator>(entity) {
+        return Some(f(&mut *generator));
+    }
+
+    None
+}
+
+/// Wake a sleeping block up, eg. because its items changed
+pub fn wake(ecs: &World, entity: Entity) {
+    if let Ok(mut ticker) = ecs.get::<&mut Ticker>(entity) {
+        ticker.wake();
+    }
+}
+
+/// Decides which blocks get ticked each frame
+#[derive(Debug)]
+pub struct TickScheduler {
+    /// Most blocks ticked in one frame, any left over keep their time for a later frame
+    pub budget: usize,
+    /// Last block ticked, the next frame carries on after it
+    cursor: u64,
+    /// Seconds of game time so far
+    pub now: f64,
+}
+
+impl Default for TickScheduler {
+    fn default() -> Self {
+        Self {
+            budget: 1024,
+            cursor: 0,
+            now: 0.,
+        }
+    }
+}
+
+impl TickScheduler {
+    /// Pick which due blocks are ticked this frame, taking turns when there's more than the budget
+    pub fn turns(&mut self, mut due: Vec<Entity>) -> Vec<Entity> {
+        due.sort_by_key(|entity| entity.to_bits());
+
+        let start = due.partition_point(|entity| entity.to_bits().get() <= self.cursor);
+        due.rotate_left(start);
+        due.truncate(self.budget);
+
+        if let Some(last) = due.last() {
+            self.cursor = last.to_bits().get();
+        }
+
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use hecs::World;
+    use rustc_hash::{FxHashMap, FxHashSet};
+
+    use crate::{
+        container::{Container, Side},
+        data::{item::ItemType, recipe::Recipe},
+        entity::{
+            components::{Crafter, CraftingJob, Furnace, Generator, JobTarget, Ticker},
+            systems::block_tick,
+        },
+        item::ItemStack,
+        tick::{TickScheduler, Tickable, wake},
+    };
+
+    fn crafter(stone: usize) -> Crafter {
+        let mut crafter = Crafter {
+            juice_per_second: 1.,
+            power: 1.,
+            ..Default::default()
+        };
+        crafter.queue.push(CraftingJob {
+            recipe: Recipe {
+                inputs: FxHashMap::from_iter([(ItemType::Stone, 1)]),
+                outputs: vec![(ItemType::DarkStone, 1)],
+                byproducts: vec![],
+                catalysts: vec![],
+                crafting_juice_cost: 1.,
+                unlock: vec![],
+                automation_crafts: 0,
+            },
+            target: JobTarget::Repeat(10),
+        });
+        crafter.input.add_item(ItemType::Stone, stone);
+        crafter
+    }
+
+    #[test]
+    fn test_idle_crafter_sleeps() {
+        let mut ecs = World::new();
+        let mut scheduler = TickScheduler::default();
+
+        // An idle crafter first shouldn't hold up the ones after it
+        let idle = ecs.spawn((crafter(0), Ticker::every(0.5)));
+        let busy = ecs.spawn((crafter(5), Ticker::every(0.5)));
+
+        // Clocks start on the first tick they're seen
+        let tick = |ecs: &mut World, scheduler: &mut TickScheduler| {
+            block_tick(
+                ecs,
+                scheduler,
+                &FxHashSet::default(),
+                &Duration::from_millis(500),
+            )
+        };
+        (0..5).for_each(|_| tick(&mut ecs, &mut scheduler));
+        assert_eq!(
+            ecs.get::<&Crafter>(busy).unwrap().output.items[ItemType::DarkStone],
+            2
+        );
+        assert!(ecs.get::<&Ticker>(idle).unwrap().asleep);
+
+        // Sleepers start again once they've got something to do
+        ecs.get::<&mut Crafter>(idle)
+            .unwrap()
+            .input
+            .add_item(ItemType::Stone, 1);
+        wake(&ecs, idle);
+        (0..3).for_each(|_| tick(&mut ecs, &mut scheduler));
+        assert_eq!(
+            ecs.get::<&Crafter>(idle).unwrap().output.items[ItemType::DarkStone],
+            1
+        );
+    }
+
+    #[test]
+    fn test_wake_busy() {
+        let mut ecs = World::new();
+        let mut scheduler = TickScheduler::default();
+        let woken = ecs.spawn((crafter(5), Ticker::every(0.5)));
+        let left = ecs.spawn((crafter(5), Ticker::every(0.5)));
+
+        // Waking a machine that's already awake, eg. as items move in, doesn't restart its clock
+        (0..5).for_each(|_| {
+            wake(&ecs, woken);
+            block_tick(
+                &mut ecs,
+                &mut scheduler,
+                &FxHashSet::default(),
+                &Duration::from_millis(500),
+            );
+        });
+        let made = |entity| ecs.get::<&Crafter>(entity).unwrap().output.items[ItemType::DarkStone];
+        assert_eq!(made(woken), made(left));
+        assert_eq!(made(woken), 2);
+    }
+
+    #[test]
+    fn test_catch_up() {
+        let mut ticked = crafter(20);
+        ticked.queue[0].target = JobTarget::Repeat(6);
+        ticked.queue.push(CraftingJob {
+            target: JobTarget::Stock(10),
+            ..ticked.queue[0].clone()
+        });
+        let mut caught_up = ticked.clone();
+
+        (0..200).for_each(|_| {
+            ticked.tick(0.125);
+        });
+        caught_up.tick(25.);
+
+        // Both jobs are done, with the juice left over from the last craft
+        assert_eq!(ticked.output.items, caught_up.output.items);
+        assert_eq!(ticked.input.items, caught_up.input.items);
+        assert_eq!(ticked.queue.len(), caught_up.queue.len());
+        assert_eq!(ticked.crafting_juice, caught_up.crafting_juice);
+        assert_eq!(caught_up.output.items[ItemType::DarkStone], 10);
+
+        let mut ticked = Furnace::default();
+        ticked.burner.fuel.add_item(ItemType::Coal, 2);
+        ticked.input.add_item(ItemType::Copper, 3);
+        ticked.input.add_item(ItemType::Tin, 3);
+        let mut caught_up = ticked.clone();
+
+        (0..160).for_each(|_| {
+            ticked.tick(0.125);
+        });
+        caught_up.tick(20.);
+
+        // Fuel is only burnt while cooking, so it isn't wasted after the ingredients run out
+        assert_eq!(ticked.output.items, caught_up.output.items);
+        assert_eq!(ticked.burner.burn_time, caught_up.burner.burn_time);
+        assert_eq!(ticked.burner.fuel.items, caught_up.burner.fuel.items);
+        assert_eq!(caught_up.output.items[ItemType::Bronze], 6);
+        assert_eq!(caught_up.burner.burn_time, 8.);
+
+        let mut ticked = Generator::burner(3.);
+        ticked.load = 0.75;
+        ticked
+            .burner
+            .as_mut()
+            .unwrap()
+            .fuel
+            .add_item(ItemType::Coal, 2);
+        let mut caught_up = ticked.clone();
+
+        (0..160).for_each(|_| {
+            ticked.tick(0.125);
+        });
+        caught_up.tick(20.);
+
+        // Only part of the output was used, so only that much of the time's worth of fuel is burnt
+        let (ticked, caught_up) = (ticked.burner.unwrap(), caught_up.burner.unwrap());
+        assert_eq!(ticked.burn_time, caught_up.burn_time);
+        assert_eq!(ticked.fuel.items, caught_up.fuel.items);
+        assert_eq!(caught_up.fuel.items[ItemType::Coal], 0);
+        assert_eq!(caught_up.burn_time, 5.);
+    }
+
+    #[test]
+    fn test_furnace() {
+        let mut furnace = Furnace::default();
+
+        // Coal put in directly is burnt, from the top it's smelted
+        furnace.insert(ItemStack::Counted(ItemType::Coal, 1), None, false);
+        furnace.insert(ItemStack::Counted(ItemType::Iron, 1), Some(Side::Up), false);
+        furnace.insert(ItemStack::Counted(ItemType::Coal, 1), Some(Side::Up), false);
+        assert_eq!(furnace.burner.fuel.items[ItemType::Coal], 1);
+        assert_eq!(furnace.input.items[ItemType::Coal], 1);
+
+        // Not hot enough yet
+        furnace.tick(5.);
+        assert_eq!(furnace.output.items[ItemType::Steel], 0);
+
+        furnace.tick(4.);
+        assert_eq!(furnace.output.items[ItemType::Steel], 1);
+        assert_eq!(furnace.input.items[ItemType::Iron], 0);
+
+        // Nothing left to cook, so the rest of the fuel is saved
+        assert_eq!(furnace.burner.burn_time, 2.);
+        furnace.tick(1.);
+        assert_eq!(furnace.burner.burn_time, 2.);
+    }
+
+    #[test]
+    fn test_fair_turns() {
+        let mut ecs = World::new();
+        let entities = (0..3).map(|_| ecs.spawn(())).collect::<Vec<_>>();
+        let mut scheduler = TickScheduler {
+            budget: 2,
+            ..Default::default()
+        };
+
+        // Everyone gets a go before anyone gets a second one
+        assert_eq!(scheduler.turns(entities.clone()), entities[0..2]);
+        assert_eq!(
+            scheduler.turns(entities.clone()),
+            vec![entities[2], entities[0]]
+        );
+    }
+}
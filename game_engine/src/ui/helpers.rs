@@ -11,6 +11,8 @@ use enum_map::EnumMap;
 
 use crate::{
     data::{item::ItemType, loader::ITEMS, recipe::Recipe},
+    entity::components::Inventory,
+    power::NetworkStatus,
     ui::Icon,
 };
 
@@ -34,6 +36,21 @@ pub fn draw_recipe(ui: &mut Ui, recipe: &Recipe, icon_size: f32, font_size: f32)
                         .draw(ui);
                     });
 
+                    // Catalysts are needed but usually not used up
+                    recipe.catalysts.iter().for_each(|catalyst| {
+                        Icon {
+                            texture: &items[catalyst.item].texture,
+                            size: icon_size,
+                            count: Some(catalyst.count),
+                            font_size,
+                        }
+                        .draw(ui)
+                        .on_hover_text(format!(
+                            "Catalyst, {:.0}% chance to be consumed",
+                            catalyst.consume_chance * 100.
+                        ));
+                    });
+
                     // Space between input & output
                     let mut arrow = text::LayoutJob::default();
                     arrow.append(
@@ -47,14 +64,30 @@ pub fn draw_recipe(ui: &mut Ui, recipe: &Recipe, icon_size: f32, font_size: f32)
                     ui.add_sized(Vec2::splat(icon_size), Label::new(arrow).selectable(false));
 
                     // Outputs on the right
-                    let (item, count) = recipe.output;
-                    Icon {
-                        texture: &items[item].texture,
-                        size: icon_size,
-                        count: Some(count),
-                        font_size,
-                    }
-                    .draw(ui);
+                    recipe.outputs.iter().for_each(|(item, count)| {
+                        Icon {
+                            texture: &items[*item].texture,
+                            size: icon_size,
+                            count: Some(*count),
+                            font_size,
+                        }
+                        .draw(ui);
+                    });
+
+                    // Chance outputs last
+                    recipe.byproducts.iter().for_each(|byproduct| {
+                        Icon {
+                            texture: &items[byproduct.item].texture,
+                            size: icon_size,
+                            count: Some(byproduct.count),
+                            font_size,
+                        }
+                        .draw(ui)
+                        .on_hover_text(format!(
+                            "Byproduct, {:.0}% chance",
+                            byproduct.chance * 100.
+                        ));
+                    });
                 });
         });
 
@@ -66,7 +99,7 @@ pub fn draw_recipe(ui: &mut Ui, recipe: &Recipe, icon_size: f32, font_size: f32)
 pub fn draw_item_grid(
     ui: &mut Ui,
     tui_id: &str,
-    item_counts: &EnumMap<ItemType, usize>,
+    item_counts: impl IntoIterator<Item = (ItemType, usize)>,
     icon_size: f32,
 ) -> EnumMap<ItemType, Option<Response>> {
     let items = ITEMS.get().expect("Items info not initialised!");
@@ -87,16 +120,16 @@ pub fn draw_item_grid(
         })
         .show(|ui| {
             ui.reuse_style().add(|ui| {
-                // Draw each item icon if we have some
+                // Draw each item icon if we have some, in the order given
                 item_counts
-                    .iter()
-                    .filter(|(_, count)| **count > 0)
+                    .into_iter()
+                    .filter(|(_, count)| *count > 0)
                     .for_each(|(id, count)| {
                         // Create and draw the icon
                         let icon = Icon {
                             texture: &items[id].texture,
                             size: icon_size,
-                            count: Some(*count),
+                            count: Some(count),
                             font_size: icon_size / 2.,
                         };
                         let resp = ui.ui_add(icon);
@@ -108,6 +141,65 @@ pub fn draw_item_grid(
     responses
 }
 
+/// Show the per-instance data of unique items when hovered
+pub fn draw_instance_tooltip(resp: &Response, container: &Inventory, item: ItemType) {
+    if item.is_stackable() {
+        return;
+    }
+
+    resp.clone().on_hover_ui(|ui| {
+        container.instances_of(item).for_each(|instance| {
+            let mut line = instance.display_name().to_string();
+            if let (Some(durability), Some(max)) = (instance.durability, instance.max_durability())
+            {
+                line += &format!(" ({durability}/{max})");
+            }
+            instance
+                .modifiers
+                .iter()
+                .for_each(|m| line += &format!(" {m:?}"));
+
+            ui.label(line);
+        });
+    });
+}
+
+/// Show how full a container is
+pub fn draw_capacity(ui: &mut Ui, container: &Inventory) {
+    let capacity = &container.capacity;
+
+    let mut parts = vec![];
+    if let Some(max_slots) = capacity.max_slots {
+        parts.push(format!("Slots: {}/{max_slots}", container.used_slots()));
+    }
+    if let Some(max_weight) = capacity.max_weight {
+        parts.push(format!("Weight: {:.0}/{max_weight:.0}", container.weight()));
+    }
+
+    if !parts.is_empty() {
+        ui.label(parts.join("  "));
+    }
+}
+
+/// Show how a power network is doing
+pub fn draw_network_status(ui: &mut Ui, status: &NetworkStatus) {
+    if status.blocks == 0 {
+        ui.label("Not connected to a power network");
+        return;
+    }
+
+    ui.label(format!(
+        "Network: {} blocks  Supply: {:.1}/s  Demand: {:.1}/s",
+        status.blocks, status.supply, status.demand
+    ));
+    if status.satisfaction() < 1. {
+        ui.colored_label(
+            Color32::YELLOW,
+            format!("Running at {:.0}%", status.satisfaction() * 100.),
+        );
+    }
+}
+
 pub fn draw_progress_bar(ui: &mut Ui, width: f32, height: f32, progress: f32) {
     assert!(
         (0_f32..=1.).contains(&progress),
@@ -2,8 +2,10 @@ pub mod axes;
 pub mod debug;
 pub mod helpers;
 
+use std::time::{Duration, Instant};
+
 use axes::Axes;
-use egui::{Color32, Context, FontId, ImageSource, Response, Ui, Vec2};
+use egui::{Align2, Area, Color32, Context, FontId, ImageSource, Response, RichText, Ui, Vec2};
 use egui_taffy::{TuiBuilderLogic, TuiWidget};
 use egui_wgpu::{Renderer, ScreenDescriptor};
 use egui_winit::State;
@@ -18,6 +20,7 @@ use crate::{
         components::UIType,
         systems::ui::{draw_crafting_window, draw_hotbar, draw_inventory, draw_ui},
     },
+    event::{Message, Subscriber, messages::TransferFailedMessage},
     render::{camera::Camera, context::DrawContext},
     state::game::GameState,
     ui::debug::DEBUG_WINDOW,
@@ -31,11 +34,17 @@ pub trait Drawable {
     fn show_widget(&self, _ui: &mut Ui) {}
 }
 
+/// How long a failed transfer is shown for
+const TRANSFER_FAILURE_DURATION: Duration = Duration::from_secs(3);
+
 pub struct UI {
     // Rendering
     pub egui_state: State,
     egui_context: Context,
     pub egui_renderer: Renderer,
+
+    /// Recent transfers which couldn't be done, shown above the hotbar
+    transfer_failures: Vec<(Instant, TransferFailedMessage)>,
 }
 
 impl UI {
@@ -58,6 +67,7 @@ impl UI {
             egui_state,
             egui_context,
             egui_renderer,
+            transfer_failures: vec![],
         }
     }
 
@@ -70,7 +80,11 @@ impl UI {
         view: &TextureView,
         game: &GameState,
     ) {
+        self.transfer_failures
+            .retain(|(time, _)| time.elapsed() < TRANSFER_FAILURE_DURATION);
+
         let inputs = self.egui_state.take_egui_input(&draw_context.window);
+        let transfer_failures = &self.transfer_failures;
         let output = self.egui_context.run(inputs, |ctx| {
             // UI code here
             DEBUG_WINDOW.show_window(ctx);
@@ -98,13 +112,33 @@ impl UI {
                         block_entity.has::<UIType>(),
                         "Entity doesn't have a UI component!"
                     );
-                    draw_ui(ctx, block_entity);
+                    draw_ui(ctx, block_entity, player_entity);
 
                     // TODO: Only show inventory when block has some interaction with it?
                     draw_inventory(ctx, player_entity);
                 }
             }
             draw_hotbar(ctx, player_entity);
+
+            // Only the player's own transfers are worth telling them about
+            Area::new("Transfer failures".into())
+                .anchor(Align2::CENTER_BOTTOM, [0., -60.])
+                .interactable(false)
+                .show(ctx, |ui| {
+                    transfer_failures
+                        .iter()
+                        .filter(|(_, m)| m.source == game.player || m.dest == game.player)
+                        .for_each(|(_, m)| {
+                            ui.label(
+                                RichText::new(format!(
+                                    "Couldn't move {}: {}",
+                                    m.item.data().name,
+                                    m.reason
+                                ))
+                                .color(Color32::LIGHT_RED),
+                            );
+                        });
+                });
         });
 
         let screen_descriptor = ScreenDescriptor {
@@ -168,6 +202,14 @@ impl UI {
     }
 }
 
+impl Subscriber for UI {
+    fn handle_message(&mut self, event: &Message) {
+        if let Message::TransferFailed(m) = event {
+            self.transfer_failures.push((Instant::now(), m.clone()));
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Icon<'a> {
     pub texture: &'a ImageSource<'static>,